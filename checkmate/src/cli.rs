@@ -18,6 +18,7 @@ use clap::{
     Args,
     Parser,
     Subcommand,
+    ValueEnum,
 };
 use std::path::PathBuf;
 
@@ -38,8 +39,10 @@ pub struct Cli {
 pub enum Commands {
     /// Validate the provided configuration file.
     Lint(Lint),
+    /// Render the effective configuration as checkmate will apply it.
+    Render(Render),
     /// Apply the provided configuration to the checkmk site.
-    Apply(Apply),
+    Apply(Box<Apply>),
 }
 
 #[derive(Debug, Args)]
@@ -50,6 +53,31 @@ pub struct Lint {
     /// Print the internal representation of the configuration file after loading it.
     #[arg(long)]
     pub print_config: bool,
+    /// The checkmk site the configuration will be applied to.
+    ///
+    /// If provided, the `site` attributes of folders and hosts are verified to refer to either
+    /// this site or a declared site. `apply` always verifies them against the existing sites.
+    #[arg(long, env = "CHECKMATE_CHECKMK_SITE")]
+    pub site: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct Render {
+    /// The configuration file to use.
+    #[arg(long, default_value = "checkmate.yaml", env = "CHECKMATE_CONFIG_FILE")]
+    pub config_file: PathBuf,
+    /// The format to render the configuration in.
+    #[arg(long, value_enum, default_value_t = RenderFormat::Yaml)]
+    pub format: RenderFormat,
+    /// Include secrets, like SNMP and IPMI passwords, instead of redacting them.
+    #[arg(long)]
+    pub show_secrets: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum RenderFormat {
+    Yaml,
+    Json,
 }
 
 #[derive(Debug, Args)]
@@ -74,4 +102,59 @@ pub struct Apply {
     /// The configuration file to use.
     #[arg(long, default_value = "checkmate.yaml", env = "CHECKMATE_CONFIG_FILE")]
     pub config_file: PathBuf,
+    /// The maximum time in seconds to wait for a connection to the checkmk server.
+    #[arg(long, env = "CHECKMATE_CHECKMK_CONNECT_TIMEOUT")]
+    pub connect_timeout: Option<u64>,
+    /// The maximum time in seconds a request to the checkmk server may take.
+    ///
+    /// This includes waiting for the activation of changes to complete.
+    #[arg(long, env = "CHECKMATE_CHECKMK_REQUEST_TIMEOUT")]
+    pub request_timeout: Option<u64>,
+    /// A PEM-file of CA certificates to trust in addition to the system roots.
+    ///
+    /// Can be provided multiple times.
+    #[arg(long, env = "CHECKMATE_CHECKMK_CA_BUNDLE")]
+    pub ca_bundle: Vec<PathBuf>,
+    /// Do not verify the certificate of the checkmk server.
+    ///
+    /// This makes the connection vulnerable to man-in-the-middle attacks, and should only be used
+    /// for testing.
+    #[arg(long)]
+    pub insecure: bool,
+    /// The URL of a proxy to send all requests through.
+    ///
+    /// By default, the proxies configured through `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY` are
+    /// used.
+    #[arg(long, env = "CHECKMATE_CHECKMK_PROXY", conflicts_with = "no_proxy")]
+    pub proxy: Option<String>,
+    /// Ignore the proxies configured through the environment.
+    #[arg(long)]
+    pub no_proxy: bool,
+    /// A PEM-file containing the client certificate and private key to authenticate with, if the
+    /// checkmk server requires mutual TLS.
+    #[arg(long, env = "CHECKMATE_CHECKMK_CLIENT_IDENTITY")]
+    pub client_identity: Option<PathBuf>,
+    /// The path of the checkmk REST API relative to the server URL.
+    ///
+    /// Defaults to `/<site>/check_mk/api/1.0`, and only has to be changed if a reverse proxy
+    /// exposes the API at a different path.
+    #[arg(long, env = "CHECKMATE_CHECKMK_API_BASE_PATH")]
+    pub api_base_path: Option<String>,
+    /// The maximum number of attempts for a request to the checkmk server that failed transiently.
+    ///
+    /// Requests that create objects or trigger actions, like activating changes, are only retried
+    /// if they could not be sent at all, so they are never applied twice. Set to 1 to disable
+    /// retries.
+    ///
+    /// Independent of this, objects that are modified on the site while they are being applied are
+    /// applied again, up to 3 times in total.
+    #[arg(long, env = "CHECKMATE_CHECKMK_MAX_ATTEMPTS", default_value_t = 3, value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_attempts: u32,
+    /// The key used to fingerprint declared secrets, like passwords and user secrets.
+    ///
+    /// Checkmk never reports secrets, so checkmate stores a keyed fingerprint of them on the site
+    /// to detect when they changed. Required if the configuration declares any secrets, and must
+    /// stay the same across runs, since changing it updates all secrets once.
+    #[arg(long, env = "CHECKMATE_FINGERPRINT_KEY")]
+    pub fingerprint_key: Option<String>,
 }
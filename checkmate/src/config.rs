@@ -14,34 +14,194 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+mod bi;
+mod dcd_connections;
 mod folders;
+mod groups;
 mod hosts;
+mod notification_rules;
+mod passwords;
 mod rulesets;
+mod secrets;
+mod sites;
+mod tags;
+mod time_periods;
+mod users;
 
+pub use bi::Bi;
+pub use dcd_connections::DcdConnection;
 pub use folders::{
     Folder,
     Folders,
 };
+pub use groups::{
+    prune_groups,
+    Group,
+};
 pub use hosts::Host;
+pub use notification_rules::{
+    apply_notification_rules,
+    NotificationRule,
+};
+pub use passwords::{
+    expand_password_references,
+    Password,
+};
 pub use rulesets::Ruleset;
+pub use secrets::SecretRef;
+pub use sites::Site;
+pub use tags::{
+    AuxTag,
+    TagGroup,
+};
+pub use time_periods::{
+    ordered_time_periods,
+    TimePeriod,
+    BUILTIN_TIME_PERIOD,
+};
+pub use users::{
+    remove_orphaned_users,
+    User,
+    UserRemovalPolicy,
+};
 
+use checkmk_client::{
+    groups::GroupType,
+    models::{
+        builtin_tag_ids,
+        BUILTIN_AUX_TAG_IDS,
+    },
+    rules::HostTagsConditionOperator,
+};
 use color_eyre::eyre::{
     Result,
     WrapErr,
 };
-use serde::Deserialize;
+use serde::{
+    Deserialize,
+    Serialize,
+};
 use std::{
-    collections::HashMap,
+    collections::{
+        BTreeMap,
+        BTreeSet,
+        HashMap,
+        HashSet,
+    },
     path::Path,
 };
 
 /// The central type describing the declarative configuration which will be applied to the desired
 /// check_mk site.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct DeclarativeConfig {
+    /// The custom host attributes that exist on the site.
+    ///
+    /// If provided, hosts and folders can only set attributes not modelled by checkmate if they
+    /// are declared here.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_attributes: Option<BTreeSet<String>>,
+    #[serde(
+        default,
+        deserialize_with = "crate::de::deserialize_map_with_ids",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub aux_tags: BTreeMap<String, AuxTag>,
+    #[serde(
+        default,
+        deserialize_with = "crate::de::deserialize_map_with_ids",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub tag_groups: BTreeMap<String, TagGroup>,
+    #[serde(
+        default,
+        deserialize_with = "crate::de::deserialize_map_with_ids",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub host_groups: BTreeMap<String, Group>,
+    #[serde(
+        default,
+        deserialize_with = "crate::de::deserialize_map_with_ids",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub service_groups: BTreeMap<String, Group>,
+    #[serde(
+        default,
+        deserialize_with = "crate::de::deserialize_map_with_ids",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub contact_groups: BTreeMap<String, Group>,
+    /// If set, host, service and contact groups whose name starts with this prefix are considered
+    /// to be managed by checkmate, and will be removed from the site if they are not declared.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub managed_group_prefix: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "crate::de::deserialize_map_with_ids",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub time_periods: BTreeMap<String, TimePeriod>,
+    #[serde(
+        default,
+        deserialize_with = "crate::de::deserialize_map_with_ids",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub users: BTreeMap<String, User>,
+    /// What happens to users on the site that are not declared. By default they are kept.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub removed_users: Option<UserRemovalPolicy>,
+    /// Users on the site that are not declared, but must never be deactivated or deleted. The user
+    /// checkmate authenticates as is always considered unmanaged.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unmanaged_users: Vec<String>,
+    /// Entries of the password store, which rules can refer to as `{{password:<id>}}` in their
+    /// `value_raw`.
+    #[serde(
+        default,
+        deserialize_with = "crate::de::deserialize_map_with_ids",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub passwords: BTreeMap<String, Password>,
+    /// Notification rules managed by checkmate, in the order they are evaluated.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notification_rules: Vec<NotificationRule>,
+    #[serde(
+        default,
+        deserialize_with = "crate::de::deserialize_map_with_ids",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub dcd_connections: BTreeMap<String, DcdConnection>,
+    #[serde(default, skip_serializing_if = "Bi::is_empty")]
+    pub bi: Bi,
+    /// Remote sites for distributed monitoring, which hosts and folders can refer to through
+    /// their `site` attribute.
+    #[serde(
+        default,
+        deserialize_with = "crate::de::deserialize_map_with_ids",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub sites: BTreeMap<String, Site>,
     pub folders: Folders,
 }
 
+/// Rulesets whose rules have the name of a time period as their value.
+const TIME_PERIOD_RULESETS: &[&str] = &[
+    "extra_host_conf:check_period",
+    "extra_host_conf:notification_period",
+    "extra_host_conf:service_period",
+    "extra_service_conf:check_period",
+    "extra_service_conf:notification_period",
+    "extra_service_conf:service_period",
+];
+
+/// A tag that is used somewhere in the configuration, either as an attribute of a folder or host,
+/// or in the conditions of a rule.
+struct TagUsage<'a> {
+    used_by: String,
+    group_id: &'a str,
+    tag_id: &'a str,
+}
+
 impl DeclarativeConfig {
     pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
         let config: DeclarativeConfig = serde_yaml::from_reader(std::fs::File::open(path)?)
@@ -52,8 +212,7 @@ impl DeclarativeConfig {
 
     fn verify_constraints(&self) -> Result<()> {
         let mut discovered_hosts: HashMap<&str, &Path> = HashMap::new();
-        let mut folders_to_visit = vec![&self.folders.root_folder];
-        while let Some(folder) = folders_to_visit.pop() {
+        for folder in self.folders.iter() {
             for host in folder.hosts.iter().flatten() {
                 if let Some(existing_path) = discovered_hosts.insert(&host.host_name, &folder.path)
                 {
@@ -65,9 +224,770 @@ impl DeclarativeConfig {
                     );
                 }
             }
-            folders_to_visit.extend(folder.folders.values());
+        }
+
+        for folder in self.folders.iter() {
+            for host in folder.hosts.iter().flatten() {
+                for node in host.cluster_nodes.iter().flatten() {
+                    match self.find_host(node) {
+                        None => color_eyre::eyre::bail!(
+                            "Cluster host {} has node {}, which is not a declared host",
+                            host.host_name,
+                            node
+                        ),
+                        // Cluster hosts are created after all other hosts, so a cluster can't be
+                        // the node of another cluster.
+                        Some(node_host) if node_host.is_cluster() => color_eyre::eyre::bail!(
+                            "Cluster host {} has node {}, which is a cluster host itself",
+                            host.host_name,
+                            node
+                        ),
+                        Some(_) => {}
+                    }
+                }
+            }
+        }
+
+        for folder in self.folders.iter() {
+            for host in folder.hosts.iter().flatten() {
+                for (ruleset_name, ruleset) in &host.rulesets {
+                    for rule in &ruleset.rules {
+                        if rule.conditions.host_name.is_some() {
+                            color_eyre::eyre::bail!(
+                                "Rule {} of ruleset {} on host {} has a host name condition, \
+                                 which checkmate generates for rules declared on hosts",
+                                rule.custom_id,
+                                ruleset_name,
+                                host.host_name
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(managed_group_prefix) = &self.managed_group_prefix {
+            for folder in self.folders.iter() {
+                let attributes = folder
+                    .attributes
+                    .iter()
+                    .map(|attributes| {
+                        (
+                            format!("folder {}", folder.path.display()),
+                            &attributes.contact_groups,
+                        )
+                    })
+                    .chain(folder.hosts.iter().flatten().filter_map(|host| {
+                        host.attributes.as_ref().map(|attributes| {
+                            (
+                                format!("host {}", host.host_name),
+                                &attributes.contact_groups,
+                            )
+                        })
+                    }));
+                for (used_by, contact_groups) in attributes {
+                    for contact_group in contact_groups.iter().flat_map(|c| &c.groups) {
+                        self.verify_managed_contact_group(
+                            managed_group_prefix,
+                            contact_group,
+                            &used_by,
+                        )?;
+                    }
+                }
+            }
+            for user in self.users.values() {
+                for contact_group in &user.contact_groups {
+                    self.verify_managed_contact_group(
+                        managed_group_prefix,
+                        contact_group,
+                        &format!("user {}", user.username),
+                    )?;
+                }
+            }
+        }
+
+        self.verify_time_periods()?;
+        self.verify_password_references()?;
+        self.verify_notification_rules()?;
+        self.verify_bi()?;
+
+        for dcd_connection in self.dcd_connections.values() {
+            for creation_rule in &dcd_connection.creation_rules {
+                let is_declared = self
+                    .folders
+                    .iter()
+                    .any(|folder| folder.path == Path::new(&creation_rule.folder_path));
+                if !is_declared {
+                    color_eyre::eyre::bail!(
+                        "DCD connection {} creates hosts in folder {}, which is not declared",
+                        dcd_connection.id,
+                        creation_rule.folder_path
+                    );
+                }
+            }
+        }
+
+        for username in &self.unmanaged_users {
+            if self.users.contains_key(username) {
+                color_eyre::eyre::bail!(
+                    "User {} is both declared and listed as an unmanaged user",
+                    username
+                );
+            }
+        }
+
+        if let Some(custom_attributes) = &self.custom_attributes {
+            for folder in self.folders.iter() {
+                if let Some(attributes) = &folder.attributes {
+                    verify_extra_attributes(
+                        custom_attributes,
+                        attributes.extra_attributes.iter().map(|(name, _)| name),
+                    )
+                    .wrap_err_with(|| {
+                        format!("Invalid attributes on folder {}", folder.path.display())
+                    })?;
+                }
+                for host in folder.hosts.iter().flatten() {
+                    if let Some(attributes) = &host.attributes {
+                        verify_extra_attributes(
+                            custom_attributes,
+                            attributes.extra_attributes.iter().map(|(name, _)| name),
+                        )
+                        .wrap_err_with(|| {
+                            format!("Invalid attributes on host {}", host.host_name)
+                        })?;
+                    }
+                }
+            }
+        }
+
+        for tag_group in self.tag_groups.values() {
+            for tag in &tag_group.tags {
+                for aux_tag in &tag.aux_tags {
+                    if !self.aux_tags.contains_key(aux_tag)
+                        && !BUILTIN_AUX_TAG_IDS.contains(&aux_tag.as_str())
+                    {
+                        color_eyre::eyre::bail!(
+                            "Tag {} of tag group {} refers to aux tag {}, which is not declared",
+                            tag.id,
+                            tag_group.id,
+                            aux_tag
+                        );
+                    }
+                }
+            }
+        }
+
+        // Tags of tag groups that are neither declared nor built-in can't be verified here, since
+        // they are specific to the site. They are verified against the site when applying.
+        for usage in self.tag_usages() {
+            let tag_ids = match self.tag_groups.get(usage.group_id) {
+                Some(tag_group) => tag_group.tag_ids().collect(),
+                None => match builtin_tag_ids(usage.group_id) {
+                    Some(tag_ids) => tag_ids.to_vec(),
+                    None => continue,
+                },
+            };
+            verify_tag_usage(&usage, &tag_ids)?;
         }
 
         Ok(())
     }
+
+    /// Whether the configuration declares secrets that are fingerprinted on the site.
+    pub fn declares_secrets(&self) -> bool {
+        !self.passwords.is_empty() || self.users.values().any(|user| user.auth.is_some())
+    }
+
+    pub fn is_time_period_declared(&self, name: &str) -> bool {
+        name == BUILTIN_TIME_PERIOD || self.time_periods.contains_key(name)
+    }
+
+    fn verify_time_periods(&self) -> Result<()> {
+        for time_period in self.time_periods.values() {
+            for excluded in &time_period.exclude {
+                if !self.time_periods.contains_key(excluded) {
+                    color_eyre::eyre::bail!(
+                        "Time period {} excludes time period {}, which is not declared",
+                        time_period.name,
+                        excluded
+                    );
+                }
+            }
+        }
+
+        // All exclusions are declared at this point, so they can be walked without checking.
+        for time_period in self.time_periods.values() {
+            // Walk all exclusions, which must never lead back to the time period itself.
+            let mut to_visit = time_period.exclude.iter().collect::<Vec<_>>();
+            let mut visited = Vec::new();
+            while let Some(excluded) = to_visit.pop() {
+                if *excluded == time_period.name {
+                    color_eyre::eyre::bail!(
+                        "Time period {} excludes itself, either directly or through other time \
+                         periods",
+                        time_period.name
+                    );
+                }
+                if !visited.contains(&excluded) {
+                    visited.push(excluded);
+                    to_visit.extend(self.time_periods[excluded].exclude.iter());
+                }
+            }
+        }
+
+        for folder in self.folders.iter() {
+            for ruleset in folder.effective_rulesets() {
+                if !TIME_PERIOD_RULESETS.contains(&ruleset.name.as_str()) {
+                    continue;
+                }
+                for rule in &ruleset.rules {
+                    let Some(time_period) = python_string_literal(&rule.value_raw) else {
+                        continue;
+                    };
+                    if !self.is_time_period_declared(time_period) {
+                        color_eyre::eyre::bail!(
+                            "Rule {} of ruleset {} in folder {} refers to time period {}, which is \
+                             not declared",
+                            rule.custom_id,
+                            ruleset.name,
+                            folder.path.display(),
+                            time_period
+                        );
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn verify_password_references(&self) -> Result<()> {
+        for folder in self.folders.iter() {
+            for ruleset in folder.effective_rulesets() {
+                for rule in &ruleset.rules {
+                    for password in &rule.password_references {
+                        if !self.passwords.contains_key(password) {
+                            color_eyre::eyre::bail!(
+                                "Rule {} of ruleset {} in folder {} refers to password {}, which \
+                                 is not declared",
+                                rule.custom_id,
+                                ruleset.name,
+                                folder.path.display(),
+                                password
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn verify_notification_rules(&self) -> Result<()> {
+        let mut ids = BTreeSet::new();
+        for notification_rule in &self.notification_rules {
+            if !ids.insert(&notification_rule.id) {
+                color_eyre::eyre::bail!(
+                    "Notification rule {} is declared more than once",
+                    notification_rule.id
+                );
+            }
+
+            if let Some(time_period) = &notification_rule.conditions.time_period {
+                if !self.is_time_period_declared(time_period) {
+                    color_eyre::eyre::bail!(
+                        "Notification rule {} refers to time period {}, which is not declared",
+                        notification_rule.id,
+                        time_period
+                    );
+                }
+            }
+
+            if let Some(managed_group_prefix) = &self.managed_group_prefix {
+                let contact_groups = notification_rule
+                    .conditions
+                    .contact_groups
+                    .iter()
+                    .chain(&notification_rule.contacts.contact_groups);
+                for contact_group in contact_groups {
+                    self.verify_managed_contact_group(
+                        managed_group_prefix,
+                        contact_group,
+                        &format!("notification rule {}", notification_rule.id),
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn verify_bi(&self) -> Result<()> {
+        // Rule and aggregation IDs are unique across all packs.
+        let mut rule_ids = BTreeMap::new();
+        for (pack, rule) in self.bi.rules() {
+            if let Some(other_pack) = rule_ids.insert(&rule.id, &pack.id) {
+                color_eyre::eyre::bail!(
+                    "BI rule {} is declared both in pack {} and pack {}",
+                    rule.id,
+                    other_pack,
+                    pack.id
+                );
+            }
+        }
+        let mut aggregation_ids = BTreeMap::new();
+        for (pack, aggregation) in self.bi.aggregations() {
+            if let Some(other_pack) = aggregation_ids.insert(&aggregation.id, &pack.id) {
+                color_eyre::eyre::bail!(
+                    "BI aggregation {} is declared both in pack {} and pack {}",
+                    aggregation.id,
+                    other_pack,
+                    pack.id
+                );
+            }
+        }
+
+        let called_rules = self
+            .bi
+            .rules()
+            .flat_map(|(pack, rule)| {
+                rule.called_rules()
+                    .map(move |called| (called, format!("rule {} of pack {}", rule.id, pack.id)))
+            })
+            .chain(self.bi.aggregations().flat_map(|(pack, aggregation)| {
+                aggregation.called_rules().map(move |called| {
+                    (
+                        called,
+                        format!("aggregation {} of pack {}", aggregation.id, pack.id),
+                    )
+                })
+            }));
+        for (called, caller) in called_rules {
+            if !self.bi.rules().any(|(_, rule)| rule.id == called) {
+                color_eyre::eyre::bail!(
+                    "BI {} calls rule {}, which is not declared",
+                    caller,
+                    called
+                );
+            }
+        }
+
+        // Walk all rules called by a rule, which must never lead back to the rule itself.
+        for (_, rule) in self.bi.rules() {
+            let mut to_visit = rule.called_rules().collect::<Vec<_>>();
+            let mut visited = Vec::new();
+            while let Some(called) = to_visit.pop() {
+                if called == rule.id {
+                    color_eyre::eyre::bail!(
+                        "BI rule {} calls itself, either directly or through other rules",
+                        rule.id
+                    );
+                }
+                if !visited.contains(&called) {
+                    visited.push(called);
+                    if let Some((_, called_rule)) =
+                        self.bi.rules().find(|(_, rule)| rule.id == called)
+                    {
+                        to_visit.extend(called_rule.called_rules());
+                    }
+                }
+            }
+        }
+
+        if let Some(managed_group_prefix) = &self.managed_group_prefix {
+            for pack in self.bi.packs.values() {
+                for contact_group in &pack.contact_groups {
+                    self.verify_managed_contact_group(
+                        managed_group_prefix,
+                        contact_group,
+                        &format!("BI pack {}", pack.id),
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Verify that the `site` attributes of folders, hosts and DCD connections refer to either a
+    /// declared site, or one of the given sites which are known to exist.
+    pub fn verify_sites(&self, existing_sites: &[&str]) -> Result<()> {
+        for (used_by, site) in self.site_usages() {
+            if !self.sites.contains_key(site) && !existing_sites.contains(&site) {
+                color_eyre::eyre::bail!(
+                    "{} refers to site {}, which is neither declared nor exists",
+                    used_by,
+                    site
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn site_usages(&self) -> Vec<(String, &str)> {
+        let mut usages = Vec::new();
+        for folder in self.folders.iter() {
+            if let Some(site) = folder.attributes.as_ref().and_then(|a| a.site.as_deref()) {
+                usages.push((format!("Folder {}", folder.path.display()), site));
+            }
+            for host in folder.hosts.iter().flatten() {
+                if let Some(site) = host.attributes.as_ref().and_then(|a| a.site.as_deref()) {
+                    usages.push((format!("Host {}", host.host_name), site));
+                }
+            }
+        }
+        for dcd_connection in self.dcd_connections.values() {
+            let used_by = format!("DCD connection {}", dcd_connection.id);
+            if let Some(site) = &dcd_connection.site {
+                usages.push((used_by.clone(), site));
+            }
+            for creation_rule in &dcd_connection.creation_rules {
+                if let Some(site) = &creation_rule.host_attributes.site {
+                    usages.push((used_by.clone(), site));
+                }
+            }
+        }
+        usages
+    }
+
+    fn verify_managed_contact_group(
+        &self,
+        managed_group_prefix: &str,
+        contact_group: &str,
+        used_by: &str,
+    ) -> Result<()> {
+        if contact_group.starts_with(managed_group_prefix)
+            && !self.contact_groups.contains_key(contact_group)
+        {
+            color_eyre::eyre::bail!(
+                "Contact group {} used by {} is managed by checkmate, but not declared",
+                contact_group,
+                used_by
+            );
+        }
+        Ok(())
+    }
+
+    /// All declared host, service and contact groups, alongside their type.
+    pub fn groups(&self) -> [(GroupType, &BTreeMap<String, Group>); 3] {
+        [
+            (GroupType::HostGroup, &self.host_groups),
+            (GroupType::ServiceGroup, &self.service_groups),
+            (GroupType::ContactGroup, &self.contact_groups),
+        ]
+    }
+
+    pub fn find_host(&self, host_name: &str) -> Option<&Host> {
+        self.folders
+            .iter()
+            .flat_map(|folder| folder.hosts.iter().flatten())
+            .find(|host| host.host_name == host_name)
+    }
+
+    /// Verify that all tags used in the configuration exist on the site, given the tag groups
+    /// that exist on the site mapped to the IDs of their tags, and the IDs of the site's aux tags.
+    pub fn verify_tags_against_site(
+        &self,
+        site_tag_groups: &HashMap<String, Vec<String>>,
+        site_aux_tags: &HashSet<String>,
+    ) -> Result<()> {
+        for usage in self.tag_usages() {
+            let tag_ids = match site_tag_groups.get(usage.group_id) {
+                Some(tag_ids) => tag_ids.iter().map(String::as_str).collect(),
+                None => match builtin_tag_ids(usage.group_id) {
+                    Some(tag_ids) => tag_ids.to_vec(),
+                    // Conditions on aux tags that only exist on the site look like tag usages.
+                    None if site_aux_tags.contains(usage.group_id) => continue,
+                    None => color_eyre::eyre::bail!(
+                        "Tag group {} used by {} does not exist on the site",
+                        usage.group_id,
+                        usage.used_by
+                    ),
+                },
+            };
+            verify_tag_usage(&usage, &tag_ids)?;
+        }
+        Ok(())
+    }
+
+    fn tag_usages(&self) -> Vec<TagUsage<'_>> {
+        let mut usages = Vec::new();
+        for folder in self.folders.iter() {
+            if let Some(attributes) = &folder.attributes {
+                usages.extend(attributes.tags.iter().map(|(group_id, tag_id)| TagUsage {
+                    used_by: format!("folder {}", folder.path.display()),
+                    group_id,
+                    tag_id,
+                }));
+            }
+            for host in folder.hosts.iter().flatten() {
+                if let Some(attributes) = &host.attributes {
+                    usages.extend(attributes.tags.iter().map(|(group_id, tag_id)| TagUsage {
+                        used_by: format!("host {}", host.host_name),
+                        group_id,
+                        tag_id,
+                    }));
+                }
+            }
+            let folder_rules =
+                folder
+                    .rulesets
+                    .iter()
+                    .flat_map(HashMap::values)
+                    .flat_map(|ruleset| {
+                        ruleset.rules.iter().map(|rule| {
+                            (
+                                format!(
+                                    "rule {} of ruleset {} in folder {}",
+                                    rule.custom_id,
+                                    ruleset.name,
+                                    folder.path.display()
+                                ),
+                                rule,
+                            )
+                        })
+                    });
+            let host_rules = folder.hosts.iter().flatten().flat_map(|host| {
+                host.rulesets
+                    .iter()
+                    .flat_map(move |(ruleset_name, ruleset)| {
+                        ruleset.rules.iter().map(move |rule| {
+                            (
+                                format!(
+                                    "rule {} of ruleset {} on host {}",
+                                    rule.custom_id, ruleset_name, host.host_name
+                                ),
+                                rule,
+                            )
+                        })
+                    })
+            });
+            for (used_by, rule) in folder_rules.chain(host_rules) {
+                for condition in &rule.conditions.host_tags {
+                    // Only single-valued conditions refer to tags of a tag group, all other
+                    // conditions either use aux tags or lists of tags.
+                    if !matches!(
+                        condition.operator,
+                        None | Some(HostTagsConditionOperator::Is)
+                            | Some(HostTagsConditionOperator::IsNot)
+                    ) {
+                        continue;
+                    }
+                    if let (Some(group_id), Some(tag_id)) = (&condition.key, &condition.value) {
+                        if self.aux_tags.contains_key(group_id)
+                            || BUILTIN_AUX_TAG_IDS.contains(&group_id.as_str())
+                        {
+                            continue;
+                        }
+                        usages.push(TagUsage {
+                            used_by: used_by.clone(),
+                            group_id,
+                            tag_id,
+                        });
+                    }
+                }
+            }
+        }
+        for dcd_connection in self.dcd_connections.values() {
+            for creation_rule in &dcd_connection.creation_rules {
+                usages.extend(creation_rule.host_attributes.tags.iter().map(
+                    |(group_id, tag_id)| TagUsage {
+                        used_by: format!("DCD connection {}", dcd_connection.id),
+                        group_id,
+                        tag_id,
+                    },
+                ));
+            }
+        }
+        usages
+    }
+}
+
+/// Returns the content of `value_raw` if it is a simple Python string literal.
+fn python_string_literal(value_raw: &str) -> Option<&str> {
+    let value_raw = value_raw.trim();
+    ['\'', '"'].into_iter().find_map(|quote| {
+        value_raw
+            .strip_prefix(quote)
+            .and_then(|value| value.strip_suffix(quote))
+    })
+}
+
+fn verify_extra_attributes<'a>(
+    custom_attributes: &BTreeSet<String>,
+    names: impl Iterator<Item = &'a str>,
+) -> Result<()> {
+    for name in names {
+        if !custom_attributes.contains(name) {
+            color_eyre::eyre::bail!(
+                "Attribute {} is neither known to checkmate nor declared as a custom attribute",
+                name
+            );
+        }
+    }
+    Ok(())
+}
+
+fn verify_tag_usage(usage: &TagUsage, tag_ids: &[&str]) -> Result<()> {
+    if !tag_ids.contains(&usage.tag_id) {
+        color_eyre::eyre::bail!(
+            "Tag {} used by {} is not valid for tag group {}, expected one of: {}",
+            usage.tag_id,
+            usage.used_by,
+            usage.group_id,
+            tag_ids.join(", ")
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse and verify the configuration.
+    fn load(yaml: &str) -> Result<DeclarativeConfig> {
+        let config: DeclarativeConfig = serde_yaml::from_str(yaml)?;
+        config.verify_constraints()?;
+        Ok(config)
+    }
+
+    /// A BI pack with the given rules, each calling the given other rules, and the root folder.
+    fn bi_config(rules: &[(&str, &[&str])]) -> String {
+        let rules = rules
+            .iter()
+            .map(|(id, called_rules)| {
+                let nodes = called_rules
+                    .iter()
+                    .map(|called| format!("{{action: {{type: call_a_rule, rule_id: {}}}}}", called))
+                    .collect::<Vec<_>>();
+                format!(
+                    "{}: {{properties: {{title: {}}}, nodes: [{}]}}",
+                    id,
+                    id,
+                    nodes.join(", ")
+                )
+            })
+            .collect::<Vec<_>>();
+        format!(
+            "bi: {{packs: {{pack: {{title: Pack, rules: {{{}}}}}}}}}\n\
+             folders: {{/: {{title: Main}}}}",
+            rules.join(", ")
+        )
+    }
+
+    /// Time periods, each excluding the given other time periods, and the root folder.
+    fn time_periods_config(time_periods: &[(&str, &[&str])]) -> String {
+        let time_periods = time_periods
+            .iter()
+            .map(|(name, excluded)| {
+                format!("{}: {{alias: {}, exclude: {:?}}}", name, name, excluded)
+            })
+            .collect::<Vec<_>>();
+        format!(
+            "time_periods: {{{}}}\n\
+             folders: {{/: {{title: Main}}}}",
+            time_periods.join(", ")
+        )
+    }
+
+    #[test]
+    fn bi_rules_are_ordered_after_the_rules_they_call() {
+        let config = load(&bi_config(&[("a", &["c"]), ("b", &[]), ("c", &["b"])])).unwrap();
+        let ordered = config
+            .bi
+            .ordered_rules()
+            .into_iter()
+            .map(|(_, rule)| rule.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ordered, ["b", "c", "a"]);
+    }
+
+    #[test]
+    fn bi_rules_must_not_call_themselves() {
+        let error = load(&bi_config(&[("a", &["b"]), ("b", &["a"])])).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "BI rule a calls itself, either directly or through other rules"
+        );
+    }
+
+    #[test]
+    fn bi_rules_must_only_call_declared_rules() {
+        let error = load(&bi_config(&[("a", &["missing"])])).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "BI rule a of pack pack calls rule missing, which is not declared"
+        );
+    }
+
+    #[test]
+    fn host_rules_come_before_the_rules_of_their_folder() {
+        let config = load(
+            r#"
+folders:
+  /:
+    title: Main
+    rulesets:
+      ruleset:
+        rules:
+        - id: folder
+          value_raw: "'folder'"
+    hosts:
+    - host_name: host
+      rulesets:
+        ruleset:
+          rules:
+          - id: host
+            value_raw: "'host'"
+"#,
+        )
+        .unwrap();
+        let rulesets = config.folders.root_folder.effective_rulesets();
+        assert_eq!(rulesets.len(), 1);
+        let rules = rulesets[0]
+            .rules
+            .iter()
+            .map(|rule| (rule.custom_id.as_str(), rule.conditions.host_name.is_some()))
+            .collect::<Vec<_>>();
+        assert_eq!(rules, [("host:host:host", true), ("folder", false)]);
+    }
+
+    #[test]
+    fn time_periods_may_exclude_the_same_time_period_through_several_paths() {
+        let config =
+            time_periods_config(&[("a", &["b", "c"]), ("b", &["d"]), ("c", &["d"]), ("d", &[])]);
+        assert!(load(&config).is_ok());
+    }
+
+    #[test]
+    fn time_periods_must_not_exclude_themselves() {
+        let error = load(&time_periods_config(&[("a", &["a"])])).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Time period a excludes itself, either directly or through other time periods"
+        );
+
+        let error = load(&time_periods_config(&[
+            ("a", &["b"]),
+            ("b", &["c"]),
+            ("c", &["a"]),
+        ]))
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Time period a excludes itself, either directly or through other time periods"
+        );
+    }
+
+    #[test]
+    fn time_periods_must_only_exclude_declared_time_periods() {
+        let error = load(&time_periods_config(&[("a", &["b"]), ("b", &["missing"])])).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Time period b excludes time period missing, which is not declared"
+        );
+    }
 }
@@ -0,0 +1,311 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    de::DeclaredById,
+    Result,
+};
+use checkmk_client::bi::{
+    BiAction,
+    BiAggregation as ApiBiAggregation,
+    BiAggregationConfig,
+    BiApi,
+    BiNode,
+    BiPack as ApiBiPack,
+    BiRule as ApiBiRule,
+    BiRuleConfig,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use std::collections::BTreeMap;
+
+/// The Business Intelligence (BI) configuration.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Bi {
+    /// The BI packs managed by checkmate. Packs that are not declared here are never touched.
+    #[serde(
+        default,
+        deserialize_with = "crate::de::deserialize_map_with_ids",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub packs: BTreeMap<String, BiPack>,
+}
+
+impl Bi {
+    pub fn is_empty(&self) -> bool {
+        self.packs.is_empty()
+    }
+
+    /// All declared rules, alongside the pack they are part of.
+    pub fn rules(&self) -> impl Iterator<Item = (&BiPack, &BiRule)> {
+        self.packs
+            .values()
+            .flat_map(|pack| pack.rules.values().map(move |rule| (pack, rule)))
+    }
+
+    /// All declared aggregations, alongside the pack they are part of.
+    pub fn aggregations(&self) -> impl Iterator<Item = (&BiPack, &BiAggregation)> {
+        self.packs.values().flat_map(|pack| {
+            pack.aggregations
+                .values()
+                .map(move |aggregation| (pack, aggregation))
+        })
+    }
+
+    /// All declared rules, ordered such that rules come after all declared rules they call.
+    pub fn ordered_rules(&self) -> Vec<(&BiPack, &BiRule)> {
+        fn visit<'a>(
+            pack: &'a BiPack,
+            rule: &'a BiRule,
+            bi: &'a Bi,
+            ordered: &mut Vec<(&'a BiPack, &'a BiRule)>,
+        ) {
+            if ordered.iter().any(|(_, ordered)| ordered.id == rule.id) {
+                return;
+            }
+            for called in rule.called_rules() {
+                if let Some((pack, rule)) = bi.rules().find(|(_, rule)| rule.id == called) {
+                    visit(pack, rule, bi, ordered);
+                }
+            }
+            ordered.push((pack, rule));
+        }
+
+        let mut ordered = Vec::new();
+        for (pack, rule) in self.rules() {
+            visit(pack, rule, self, &mut ordered);
+        }
+        ordered
+    }
+
+    pub fn apply_to_site(&self, cmk: &checkmk_client::Client) -> Result<()> {
+        // Packs have to exist before their rules, and rules have to exist before the rules and
+        // aggregations calling them.
+        for pack in self.packs.values() {
+            pack.apply_to_site(cmk)?;
+        }
+        for (pack, rule) in self.ordered_rules() {
+            rule.apply_to_site(&pack.id, cmk)?;
+        }
+        for (pack, aggregation) in self.aggregations() {
+            aggregation.apply_to_site(&pack.id, cmk)?;
+        }
+        for pack in self.packs.values() {
+            pack.remove_orphaned_members(cmk)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BiPack {
+    #[serde(default, skip_serializing)]
+    pub id: String,
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contact_groups: Vec<String>,
+    #[serde(default)]
+    pub public: bool,
+    #[serde(
+        default,
+        deserialize_with = "crate::de::deserialize_map_with_ids",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub rules: BTreeMap<String, BiRule>,
+    #[serde(
+        default,
+        deserialize_with = "crate::de::deserialize_map_with_ids",
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub aggregations: BTreeMap<String, BiAggregation>,
+}
+
+impl DeclaredById for BiPack {
+    fn set_id(&mut self, id: String) {
+        self.id = id;
+    }
+}
+
+impl BiPack {
+    fn pack(&self) -> ApiBiPack {
+        ApiBiPack {
+            title: self.title.clone(),
+            contact_groups: self.contact_groups.clone(),
+            public: self.public,
+        }
+    }
+
+    fn apply_to_site(&self, cmk: &checkmk_client::Client) -> Result<()> {
+        let id = &self.id;
+        let pack = self.pack();
+        match cmk.bi().show_bi_pack(id) {
+            Ok(pack_api) if pack != pack_api.extensions => {
+                println!("[BI] {}: updating existing pack", id);
+                cmk.bi().update_bi_pack(id, &pack)?;
+                Ok(())
+            }
+            Ok(_) => {
+                println!("[BI] {}: pack exists with correct attributes", id);
+                Ok(())
+            }
+            Err(error) if error.is_status(404) => {
+                println!("[BI] {}: creating missing pack", id);
+                cmk.bi().create_bi_pack(id, &pack)?;
+                Ok(())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Delete all rules and aggregations of this pack that are not declared.
+    fn remove_orphaned_members(&self, cmk: &checkmk_client::Client) -> Result<()> {
+        let members = cmk.bi().show_bi_pack(&self.id)?.members;
+        // Aggregations are removed first, since they might call orphaned rules.
+        for aggregation in members.aggregations.links {
+            if !self.aggregations.contains_key(&aggregation.title) {
+                println!(
+                    "[BI] {}: deleting orphaned aggregation {}",
+                    self.id, aggregation.title
+                );
+                cmk.bi().delete_bi_aggregation(&aggregation.title)?;
+            }
+        }
+        for rule in members.rules.links {
+            if !self.rules.contains_key(&rule.title) {
+                println!("[BI] {}: deleting orphaned rule {}", self.id, rule.title);
+                cmk.bi().delete_bi_rule(&rule.title)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BiRule {
+    #[serde(default, skip_serializing)]
+    pub id: String,
+    #[serde(flatten)]
+    pub config: BiRuleConfig,
+}
+
+impl DeclaredById for BiRule {
+    fn set_id(&mut self, id: String) {
+        self.id = id;
+    }
+}
+
+/// The IDs of all rules called by the given nodes.
+fn called_rules<'a>(nodes: impl IntoIterator<Item = &'a BiNode>) -> impl Iterator<Item = &'a str> {
+    nodes.into_iter().filter_map(|node| match &node.action {
+        BiAction::CallARule { rule_id, .. } => Some(rule_id.as_str()),
+        _ => None,
+    })
+}
+
+impl BiRule {
+    pub fn called_rules(&self) -> impl Iterator<Item = &str> {
+        called_rules(&self.config.nodes)
+    }
+
+    fn apply_to_site(&self, pack_id: &str, cmk: &checkmk_client::Client) -> Result<()> {
+        let id = &self.id;
+        let rule = ApiBiRule {
+            pack_id: pack_id.to_owned(),
+            id: id.clone(),
+            config: self.config.clone(),
+        };
+        match cmk.bi().show_bi_rule(id) {
+            // Rule IDs are unique across all packs, so the rule might belong to a pack that is not
+            // managed by checkmate, which must not be touched.
+            Ok(rule_api) if rule_api.pack_id != pack_id => color_eyre::eyre::bail!(
+                "BI rule {} of pack {} already exists in pack {} on the site",
+                id,
+                pack_id,
+                rule_api.pack_id
+            ),
+            Ok(rule_api) if rule != rule_api => {
+                println!("[BI] {}: updating existing rule", id);
+                cmk.bi().update_bi_rule(&rule)?;
+                Ok(())
+            }
+            Ok(_) => {
+                println!("[BI] {}: rule exists with correct configuration", id);
+                Ok(())
+            }
+            Err(error) if error.is_status(404) => {
+                println!("[BI] {}: creating missing rule", id);
+                cmk.bi().create_bi_rule(&rule)?;
+                Ok(())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BiAggregation {
+    #[serde(default, skip_serializing)]
+    pub id: String,
+    #[serde(flatten)]
+    pub config: BiAggregationConfig,
+}
+
+impl DeclaredById for BiAggregation {
+    fn set_id(&mut self, id: String) {
+        self.id = id;
+    }
+}
+
+impl BiAggregation {
+    pub fn called_rules(&self) -> impl Iterator<Item = &str> {
+        called_rules([&self.config.node])
+    }
+
+    fn apply_to_site(&self, pack_id: &str, cmk: &checkmk_client::Client) -> Result<()> {
+        let id = &self.id;
+        let aggregation = ApiBiAggregation {
+            pack_id: pack_id.to_owned(),
+            id: id.clone(),
+            config: self.config.clone(),
+        };
+        match cmk.bi().show_bi_aggregation(id) {
+            // Like rule IDs, aggregation IDs are unique across all packs.
+            Ok(aggregation_api) if aggregation_api.pack_id != pack_id => color_eyre::eyre::bail!(
+                "BI aggregation {} of pack {} already exists in pack {} on the site",
+                id,
+                pack_id,
+                aggregation_api.pack_id
+            ),
+            Ok(aggregation_api) if aggregation != aggregation_api => {
+                println!("[BI] {}: updating existing aggregation", id);
+                cmk.bi().update_bi_aggregation(&aggregation)?;
+                Ok(())
+            }
+            Ok(_) => {
+                println!("[BI] {}: aggregation exists with correct configuration", id);
+                Ok(())
+            }
+            Err(error) if error.is_status(404) => {
+                println!("[BI] {}: creating missing aggregation", id);
+                cmk.bi().create_bi_aggregation(&aggregation)?;
+                Ok(())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
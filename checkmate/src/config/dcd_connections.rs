@@ -0,0 +1,175 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    de::DeclaredById,
+    Result,
+};
+use checkmk_client::dcd::{
+    CreateDcdConnectionRequest,
+    DcdApi,
+    DcdConnector,
+    DcdConnectorType,
+    DcdCreationRule,
+    ShowDcdConnectionResponse,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+/// A dynamic configuration (DCD) connection, which creates hosts for piggyback data, e.g. from
+/// cloud special agents.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DcdConnection {
+    #[serde(default, skip_serializing)]
+    pub id: String,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documentation_url: Option<String>,
+    #[serde(default)]
+    pub disabled: bool,
+    /// The site the connection runs on. Defaults to the site checkmate applies the configuration
+    /// to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub site: Option<String>,
+    /// How often the connection is executed, in seconds.
+    #[serde(default = "default_interval")]
+    pub interval: u32,
+    #[serde(default = "default_true")]
+    pub discover_on_creation: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub activate_changes_interval: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_deletion_time_after_init: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_cache_age: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validity_period: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub restrict_source_hosts: Vec<String>,
+    /// The folders hosts are created in, which have to be declared.
+    pub creation_rules: Vec<DcdCreationRule>,
+}
+
+fn default_interval() -> u32 {
+    60
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl DeclaredById for DcdConnection {
+    fn set_id(&mut self, id: String) {
+        self.id = id;
+    }
+}
+
+/// Optional settings are only compared if they are declared, since Checkmk reports its defaults
+/// otherwise.
+fn declared_matches(declared: Option<u32>, actual: Option<u32>) -> bool {
+    declared.is_none() || declared == actual
+}
+
+impl DcdConnection {
+    fn connector(&self) -> DcdConnector {
+        DcdConnector {
+            connector_type: DcdConnectorType::Piggyback,
+            interval: self.interval,
+            creation_rules: self.creation_rules.clone(),
+            discover_on_creation: self.discover_on_creation,
+            activate_changes_interval: self.activate_changes_interval,
+            no_deletion_time_after_init: self.no_deletion_time_after_init,
+            max_cache_age: self.max_cache_age,
+            validity_period: self.validity_period,
+            restrict_source_hosts: self.restrict_source_hosts.clone(),
+        }
+    }
+
+    fn needs_update(&self, site: &str, dcd_connection_api: &ShowDcdConnectionResponse) -> bool {
+        let extensions = &dcd_connection_api.extensions;
+        let connector = &extensions.connector;
+        let creation_rules_api = connector
+            .creation_rules
+            .iter()
+            .cloned()
+            .map(|mut creation_rule| {
+                creation_rule.host_attributes = creation_rule.host_attributes.without_read_only();
+                creation_rule
+            })
+            .collect::<Vec<_>>();
+
+        self.title != extensions.title
+            || self.comment.as_deref().unwrap_or_default() != extensions.comment
+            || self.documentation_url.as_deref().unwrap_or_default() != extensions.documentation_url
+            || self.disabled != extensions.disabled
+            || site != extensions.site
+            || self.interval != connector.interval
+            || self.discover_on_creation != connector.discover_on_creation
+            || !declared_matches(
+                self.activate_changes_interval,
+                connector.activate_changes_interval,
+            )
+            || !declared_matches(
+                self.no_deletion_time_after_init,
+                connector.no_deletion_time_after_init,
+            )
+            || !declared_matches(self.max_cache_age, connector.max_cache_age)
+            || !declared_matches(self.validity_period, connector.validity_period)
+            || self.restrict_source_hosts != connector.restrict_source_hosts
+            || self.creation_rules != creation_rules_api
+    }
+
+    fn create(&self, site: &str, cmk: &checkmk_client::Client) -> Result<()> {
+        cmk.dcd()
+            .create_dcd_connection(&CreateDcdConnectionRequest {
+                dcd_id: &self.id,
+                title: &self.title,
+                comment: self.comment.as_deref().unwrap_or_default(),
+                documentation_url: self.documentation_url.as_deref().unwrap_or_default(),
+                disabled: self.disabled,
+                site,
+                connector: &self.connector(),
+            })
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    pub fn apply_to_site(&self, cmk: &checkmk_client::Client) -> Result<()> {
+        let id = &self.id;
+        let site = self.site.as_deref().unwrap_or(&cmk.site);
+        match cmk.dcd().show_dcd_connection(id) {
+            Ok(dcd_connection_api) if self.needs_update(site, &dcd_connection_api) => {
+                // Checkmk does not support updating DCD connections, so we replace them instead.
+                println!("{}: replacing existing DCD connection", id);
+                cmk.dcd().delete_dcd_connection(id)?;
+                self.create(site, cmk)
+            }
+            Ok(_) => {
+                println!("{}: DCD connection exists with correct attributes", id);
+                Ok(())
+            }
+            Err(error) if error.is_status(404) => {
+                println!("{}: creating missing DCD connection", id);
+                self.create(site, cmk)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
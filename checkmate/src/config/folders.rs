@@ -15,24 +15,32 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::{
+    rulesets::host_rule_marker_prefix,
     Host,
     Ruleset,
 };
 use crate::Result;
 
-use checkmk_client::folders::{
-    CreateFolderRequest,
-    FolderAttributes,
-    FoldersApi,
-    ShowFolderResponse,
-    UpdateFolderRequest,
+use checkmk_client::{
+    folders::{
+        CreateFolderRequest,
+        FolderAttributes,
+        FoldersApi,
+        ShowFolderResponse,
+        UpdateFolderRequest,
+    },
+    rules::RulesApi,
 };
 use serde::{
     Deserialize,
     Deserializer,
+    Serialize,
 };
 use std::{
-    collections::HashMap,
+    collections::{
+        BTreeMap,
+        HashMap,
+    },
     ffi::OsStr,
     path::{
         Path,
@@ -41,12 +49,80 @@ use std::{
     rc::Rc,
 };
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Folders {
-    #[serde(rename = "/", deserialize_with = "deserialize_root_folder")]
+    #[serde(
+        rename = "/",
+        deserialize_with = "deserialize_root_folder",
+        serialize_with = "crate::ser::serialize_rc"
+    )]
     pub root_folder: Rc<Folder>,
 }
 
+impl Folders {
+    /// Iterate over all folders, starting with the root folder.
+    pub fn iter(&self) -> impl Iterator<Item = &Folder> {
+        let mut folders_to_visit = vec![&*self.root_folder];
+        std::iter::from_fn(move || {
+            let folder = folders_to_visit.pop()?;
+            folders_to_visit.extend(folder.folders.values().map(|folder| &**folder));
+            Some(folder)
+        })
+    }
+
+    /// Delete rules declared on hosts from folders which no longer have any rules of that ruleset,
+    /// which happens when a host is moved to another folder, or the rules are removed from the
+    /// host.
+    ///
+    /// Rules in folders which still have rules of that ruleset are replaced when the ruleset is
+    /// applied.
+    pub fn remove_orphaned_host_rules(&self, cmk: &checkmk_client::Client) -> Result<()> {
+        // Host rules might belong to rulesets that are no longer declared at all, so all rulesets
+        // in use on the site are searched for them.
+        for ruleset in cmk.rules().list_used_rulesets()?.rulesets {
+            let ruleset_name = &ruleset.id;
+            let marker_prefix = host_rule_marker_prefix(ruleset_name);
+            let mut folders_with_ruleset = None;
+            for rule_api in cmk.rules().list_rules(ruleset_name)?.rules {
+                let is_host_rule = rule_api
+                    .extensions
+                    .properties
+                    .comment
+                    .as_ref()
+                    .is_some_and(|comment| comment.contains(&marker_prefix));
+                if !is_host_rule {
+                    continue;
+                }
+                let folders_with_ruleset = folders_with_ruleset
+                    .get_or_insert_with(|| self.folders_with_ruleset(ruleset_name));
+                if !folders_with_ruleset.contains(&rule_api.extensions.folder) {
+                    println!(
+                        "[RULESETS] {} (in folder {}): deleting rule of a host that is no longer \
+                         declared in this folder",
+                        ruleset_name, rule_api.extensions.folder
+                    );
+                    cmk.rules().delete_rule(&rule_api.id)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The paths of all folders that declare rules of the ruleset, either on the folder itself or
+    /// on its hosts.
+    fn folders_with_ruleset(&self, ruleset_name: &str) -> Vec<String> {
+        self.iter()
+            .filter(|folder| {
+                folder
+                    .effective_rulesets()
+                    .iter()
+                    .any(|ruleset| ruleset.name == ruleset_name)
+            })
+            .map(|folder| folder.path.to_string_lossy().into_owned())
+            .collect()
+    }
+}
+
 fn deserialize_root_folder<'de, D>(deserializer: D) -> std::result::Result<Rc<Folder>, D::Error>
 where
     D: Deserializer<'de>,
@@ -96,16 +172,27 @@ fn populate_folder(path: PathBuf, mut folder: Folder) -> Rc<Folder> {
     })
 }
 
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Folder {
-    #[serde(default)]
+    #[serde(default, skip_serializing)]
     pub path: PathBuf,
     pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub attributes: Option<FolderAttributes>,
-    #[serde(default, deserialize_with = "deserialize_rulesets")]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_rulesets",
+        serialize_with = "crate::ser::serialize_optional_map_values_as_rc",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub rulesets: Option<HashMap<String, Rc<Ruleset>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub hosts: Option<Vec<Host>>,
-    #[serde(flatten, deserialize_with = "crate::de::deserialize_map_values_as_rc")]
+    #[serde(
+        flatten,
+        deserialize_with = "crate::de::deserialize_map_values_as_rc",
+        serialize_with = "crate::ser::serialize_map_values_as_rc"
+    )]
     pub folders: HashMap<String, Rc<Folder>>,
 }
 
@@ -126,6 +213,7 @@ where
                         for rule in ruleset.rules.iter_mut() {
                             rule.ruleset = ruleset_weak.clone();
                             rule.normalize_properties(&name);
+                            rule.expand_password_references();
                         }
                         ruleset.name = name;
                         ruleset
@@ -159,10 +247,61 @@ impl Folder {
             .replace(std::path::MAIN_SEPARATOR, "~")
     }
 
+    /// The rulesets of this folder, including the rules declared on the hosts in this folder.
+    ///
+    /// Rules declared on hosts come before the rules declared on the folder, since they are more
+    /// specific.
+    pub fn effective_rulesets(&self) -> Vec<Rc<Ruleset>> {
+        let mut host_rules = BTreeMap::<&str, Vec<_>>::new();
+        for host in self.hosts.iter().flatten() {
+            for (ruleset_name, rule) in host.scoped_rules() {
+                host_rules.entry(ruleset_name).or_default().push(rule);
+            }
+        }
+
+        let mut rulesets = self
+            .rulesets
+            .iter()
+            .flatten()
+            .filter(|(name, _)| !host_rules.contains_key(name.as_str()))
+            .map(|(_, ruleset)| ruleset.clone())
+            .collect::<Vec<_>>();
+        for (ruleset_name, mut rules) in host_rules {
+            let folder_ruleset = self
+                .rulesets
+                .as_ref()
+                .and_then(|rulesets| rulesets.get(ruleset_name));
+            rules.extend(
+                folder_ruleset
+                    .iter()
+                    .flat_map(|ruleset| ruleset.rules.clone()),
+            );
+            let folder = self
+                .hosts
+                .iter()
+                .flatten()
+                .next()
+                .map(|host| host.folder.clone())
+                .expect("host rules are declared on a host");
+            rulesets.push(Rc::new_cyclic(|ruleset_weak| {
+                for rule in rules.iter_mut() {
+                    rule.ruleset = ruleset_weak.clone();
+                }
+                Ruleset {
+                    name: ruleset_name.to_owned(),
+                    rules,
+                    folder,
+                }
+            }));
+        }
+        rulesets.sort_by(|a, b| a.name.cmp(&b.name));
+        rulesets
+    }
+
     fn needs_update(&self, folder_api: &ShowFolderResponse) -> bool {
         let mut equal = self.title == folder_api.title;
-        equal &=
-            self.attributes.clone().unwrap_or_default() == folder_api.extensions.clone().attributes;
+        equal &= self.attributes.clone().unwrap_or_default()
+            == folder_api.extensions.attributes.clone().without_read_only();
         !equal
     }
 
@@ -180,9 +319,7 @@ impl Folder {
                 println!("{}: folder exists with correct attributes", id);
                 Ok(())
             }
-            Err(error @ checkmk_client::ClientError::HttpRequestError(_))
-                if error.is_status(404) =>
-            {
+            Err(error) if error.is_status(404) => {
                 println!("{}: creating missing folder", id);
                 cmk.folders()
                     .create_folder(&self.into())
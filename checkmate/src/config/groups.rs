@@ -0,0 +1,120 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    de::DeclaredById,
+    Result,
+};
+use checkmk_client::groups::{
+    CreateGroupRequest,
+    GroupType,
+    GroupsApi,
+    ShowGroupResponse,
+    UpdateGroupRequest,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Group {
+    #[serde(default, skip_serializing)]
+    pub name: String,
+    pub alias: String,
+}
+
+impl DeclaredById for Group {
+    fn set_id(&mut self, id: String) {
+        self.name = id;
+    }
+}
+
+fn describe(group_type: GroupType) -> &'static str {
+    match group_type {
+        GroupType::HostGroup => "host group",
+        GroupType::ServiceGroup => "service group",
+        GroupType::ContactGroup => "contact group",
+    }
+}
+
+impl Group {
+    fn needs_update(&self, group_api: &ShowGroupResponse) -> bool {
+        self.alias != group_api.alias
+    }
+
+    pub fn apply_to_site(&self, group_type: GroupType, cmk: &checkmk_client::Client) -> Result<()> {
+        let name = &self.name;
+        let kind = describe(group_type);
+        match cmk.groups(group_type).show_group(name) {
+            Ok((group_api, etag)) if self.needs_update(&group_api) => {
+                println!("{}: updating existing {}", name, kind);
+                cmk.groups(group_type)
+                    .update_group(name, etag, &self.into())
+                    .map(|_| ())
+                    .map_err(Into::into)
+            }
+            Ok(_) => {
+                println!("{}: {} exists with correct attributes", name, kind);
+                Ok(())
+            }
+            Err(error) if error.is_status(404) => {
+                println!("{}: creating missing {}", name, kind);
+                cmk.groups(group_type)
+                    .create_group(&self.into())
+                    .map(|_| ())
+                    .map_err(Into::into)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Remove all groups of the given type from the site which are managed by checkmate, i.e. whose
+/// name starts with `managed_prefix`, but which are not declared anymore.
+pub fn prune_groups(
+    group_type: GroupType,
+    groups: &BTreeMap<String, Group>,
+    managed_prefix: &str,
+    cmk: &checkmk_client::Client,
+) -> Result<()> {
+    let kind = describe(group_type);
+    for group_api in cmk.groups(group_type).list_groups()?.groups {
+        if group_api.id.starts_with(managed_prefix) && !groups.contains_key(&group_api.id) {
+            println!("{}: removing orphaned {}", group_api.id, kind);
+            cmk.groups(group_type).delete_group(&group_api.id)?;
+        }
+    }
+    Ok(())
+}
+
+impl<'a> From<&'a Group> for CreateGroupRequest<'a> {
+    fn from(group: &'a Group) -> Self {
+        Self {
+            name: &group.name,
+            alias: &group.alias,
+        }
+    }
+}
+
+impl<'a> From<&'a Group> for UpdateGroupRequest<'a> {
+    fn from(group: &'a Group) -> Self {
+        Self {
+            alias: &group.alias,
+        }
+    }
+}
@@ -14,25 +14,54 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-use super::Folder;
-use crate::Result;
+use super::{
+    rulesets::Rule,
+    Folder,
+    Ruleset,
+};
+use crate::{
+    summary::Summary,
+    Result,
+};
 
 use checkmk_client::hosts::{
+    CreateClusterHostRequest,
     CreateHostRequest,
     HostAttributes,
     HostsApi,
     ShowHostResponse,
     UpdateHostRequest,
 };
-use serde::Deserialize;
-use std::rc::Weak;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    rc::Weak,
+};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Host {
     pub host_name: String,
-    #[serde(default, deserialize_with = "crate::de::deserialize_to_empty_weak")]
+    #[serde(
+        default,
+        deserialize_with = "crate::de::deserialize_to_empty_weak",
+        skip_serializing
+    )]
     pub folder: Weak<Folder>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub attributes: Option<HostAttributes>,
+    /// The nodes of this host, if it is a cluster host.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cluster_nodes: Option<Vec<String>>,
+    /// Rules that only apply to this host. They become rules of the host's folder, with a condition
+    /// on the host's name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub rulesets: BTreeMap<String, Ruleset>,
 }
 
 impl Host {
@@ -46,32 +75,148 @@ impl Host {
         }
     }
 
-    fn needs_update(&self, host_api: &ShowHostResponse) -> bool {
-        self.attributes.clone().unwrap_or_default() != host_api.extensions.attributes
+    /// The rules declared on this host, as rules of its folder, alongside the name of their ruleset.
+    pub fn scoped_rules(&self) -> impl Iterator<Item = (&str, Rule)> {
+        self.rulesets.iter().flat_map(move |(name, ruleset)| {
+            ruleset.rules.iter().map(move |rule| {
+                let mut rule = rule.clone();
+                rule.scope_to_host(name, &self.host_name);
+                (name.as_str(), rule)
+            })
+        })
+    }
+
+    pub fn is_cluster(&self) -> bool {
+        self.cluster_nodes.is_some()
+    }
+
+    fn new_cluster_nodes(&self, host_api: &ShowHostResponse) -> Option<&[String]> {
+        let cluster_nodes = self.cluster_nodes.as_deref()?;
+        // Checkmk doesn't preserve the order of the nodes, so they are compared as sets.
+        let site_nodes: Option<BTreeSet<&String>> = host_api
+            .extensions
+            .cluster_nodes
+            .as_ref()
+            .map(|nodes| nodes.iter().collect());
+        if site_nodes != Some(cluster_nodes.iter().collect()) {
+            Some(cluster_nodes)
+        } else {
+            None
+        }
+    }
+
+    fn is_locked(host_api: &ShowHostResponse) -> bool {
+        host_api.extensions.attributes.locked_by.is_some()
+    }
+
+    /// Returns the declared attributes, with all attributes that are locked on the site replaced by
+    /// their value on the site. Declared values of locked attributes are reported as skipped.
+    fn attributes_respecting_locks(
+        &self,
+        host_api: &ShowHostResponse,
+        summary: &mut Summary,
+    ) -> Result<HostAttributes> {
+        let attributes_api = host_api.extensions.attributes.clone().without_read_only();
+        let mut attributes = self.attributes.clone().unwrap_or_default();
+        // The lock itself has to be kept, since updating the host would remove it otherwise.
+        attributes.locked_by = attributes_api.locked_by.clone();
+        attributes.locked_attributes = attributes_api.locked_attributes.clone();
+
+        let locked_attributes = attributes_api
+            .locked_attributes
+            .as_deref()
+            .unwrap_or_default();
+        if locked_attributes.is_empty() {
+            return Ok(attributes);
+        }
+
+        // Attributes are compared by the names Checkmk uses, which are only known after
+        // serialization.
+        let serde_json::Value::Object(mut attributes_json) = serde_json::to_value(&attributes)?
+        else {
+            unreachable!("host attributes are always serialized as an object");
+        };
+        let attributes_api_json = serde_json::to_value(&attributes_api)?;
+        for name in locked_attributes {
+            let value_api = attributes_api_json.get(name);
+            match attributes_json.remove(name) {
+                Some(value) if Some(&value) != value_api => summary.skip(format!(
+                    "{}: not updating attribute {}, which is locked on the site",
+                    self.host_name, name
+                )),
+                _ => {}
+            }
+            if let Some(value_api) = value_api {
+                attributes_json.insert(name.clone(), value_api.clone());
+            }
+        }
+        Ok(serde_json::from_value(serde_json::Value::Object(
+            attributes_json,
+        ))?)
     }
 
-    pub fn apply_to_site(&self, cmk: &checkmk_client::Client) -> Result<()> {
+    pub fn apply_to_site(&self, cmk: &checkmk_client::Client, summary: &mut Summary) -> Result<()> {
         let id = self.host_name.clone();
         match cmk.hosts().show_host(&id) {
             Ok((mut host_api, mut etag)) => {
+                if self.is_cluster() != host_api.extensions.is_cluster {
+                    // Checkmk does not allow converting between normal and cluster hosts, the host
+                    // has to be removed and recreated instead.
+                    color_eyre::eyre::bail!(
+                        "{}: host is {}a cluster host on the site, but is {}declared as one",
+                        id,
+                        if host_api.extensions.is_cluster {
+                            ""
+                        } else {
+                            "not "
+                        },
+                        if self.is_cluster() { "" } else { "not " },
+                    );
+                }
                 if let Some(new_path) = self.new_path(&host_api) {
-                    println!("{}: moving host to new folder", id);
-                    (host_api, etag) = cmk.hosts().move_to_folder(&id, etag, &new_path)?;
+                    if Self::is_locked(&host_api) {
+                        // Hosts locked by other tools, like DCD, are placed in their folder by
+                        // that tool, which would move it back anyway.
+                        summary.skip(format!(
+                            "{}: not moving host to folder {}, since it is locked on the site",
+                            id, new_path
+                        ));
+                    } else {
+                        println!("{}: moving host to new folder", id);
+                        (host_api, etag) = cmk.hosts().move_to_folder(&id, etag, &new_path)?;
+                    }
                 }
-                if self.needs_update(&host_api) {
+                let attributes = self.attributes_respecting_locks(&host_api, summary)?;
+                if attributes != host_api.extensions.attributes.clone().without_read_only() {
                     println!("{}: updating existing host", id);
-                    cmk.hosts().update_host(&id, etag, &self.into())?;
+                    (host_api, etag) = cmk.hosts().update_host(
+                        &id,
+                        etag,
+                        &UpdateHostRequest {
+                            attributes: &Some(attributes),
+                        },
+                    )?;
+                }
+                if let Some(cluster_nodes) = self.new_cluster_nodes(&host_api) {
+                    println!("{}: updating nodes of cluster host", id);
+                    cmk.hosts().update_nodes(&id, etag, cluster_nodes)?;
                 }
                 Ok(())
             }
-            Err(error @ checkmk_client::ClientError::HttpRequestError(_))
-                if error.is_status(404) =>
-            {
-                println!("{}: creating missing host", id);
-                cmk.hosts()
-                    .create_host(&self.into())
-                    .map(|_| ())
-                    .map_err(Into::into)
+            Err(error) if error.is_status(404) => {
+                if self.is_cluster() {
+                    println!("{}: creating missing cluster host", id);
+                    cmk.hosts()
+                        .create_cluster_host(&self.into())
+                        .map(|_| ())
+                        .map_err(Into::into)
+                } else {
+                    println!("{}: creating missing host", id);
+                    cmk.hosts()
+                        .create_host(&self.into())
+                        .map(|_| ())
+                        .map_err(Into::into)
+                }
             }
             Err(e) => Err(e.into()),
         }
@@ -92,9 +237,16 @@ impl<'a> From<&'a Host> for CreateHostRequest<'a> {
     }
 }
 
-impl<'a> From<&'a Host> for UpdateHostRequest<'a> {
+impl<'a> From<&'a Host> for CreateClusterHostRequest<'a> {
     fn from(host: &'a Host) -> Self {
         Self {
+            folder: host
+                .folder
+                .upgrade()
+                .expect("folder weak ref is broken")
+                .id(),
+            host_name: &host.host_name,
+            nodes: host.cluster_nodes.as_deref().unwrap_or_default(),
             attributes: &host.attributes,
         }
     }
@@ -0,0 +1,327 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::Result;
+use checkmk_client::notification_rules::{
+    ContactSelection,
+    Label,
+    NotificationMethod as ApiNotificationMethod,
+    NotificationRuleConditions,
+    NotificationRuleConfig,
+    NotificationRuleProperties,
+    NotificationRulesApi,
+    NotifyPlugin,
+    PluginParams,
+    ShowNotificationRuleResponse,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use std::collections::BTreeMap;
+
+const MARKER_PREFIX: &str = "[checkmate:notification_rule:";
+
+/// A notification rule. Notification rules are evaluated by Checkmk in order, so they are declared
+/// as a list rather than a map.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NotificationRule {
+    /// The user's unique identifier of the notification rule.
+    ///
+    /// This is not the rule's UUID.
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documentation_url: Option<String>,
+    #[serde(default)]
+    pub disabled: bool,
+    #[serde(default)]
+    pub allow_users_to_deactivate: bool,
+    #[serde(default)]
+    pub conditions: NotificationConditions,
+    #[serde(default)]
+    pub contacts: NotificationContacts,
+    pub method: NotificationMethod,
+}
+
+/// The conditions under which a notification rule applies. Conditions that are not set do not
+/// restrict the rule.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct NotificationConditions {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hosts: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_hosts: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub host_labels: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub host_groups: Vec<String>,
+    /// Regular expressions matching the beginning of service names.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub services: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_services: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub service_labels: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub service_groups: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contact_groups: Vec<String>,
+    /// Only notify while this time period is active.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_period: Option<String>,
+}
+
+/// Who gets notified by a notification rule.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NotificationContacts {
+    /// Notify all contacts of the host or service the notification is about.
+    #[serde(default = "default_true")]
+    pub notified_object: bool,
+    #[serde(default)]
+    pub all_users: bool,
+    #[serde(default)]
+    pub all_users_with_email: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub users: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contact_groups: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub email_addresses: Vec<String>,
+}
+
+impl Default for NotificationContacts {
+    fn default() -> Self {
+        Self {
+            notified_object: true,
+            all_users: false,
+            all_users_with_email: false,
+            users: Vec::new(),
+            contact_groups: Vec::new(),
+            email_addresses: Vec::new(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// The notification plugin that is used to send notifications, e.g. `mail` or `slack`.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NotificationMethod {
+    pub plugin: String,
+    /// The plugin specific parameters, as expected by the Checkmk REST API.
+    ///
+    /// Parameters that are not declared are left at the defaults Checkmk chooses.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub parameters: BTreeMap<String, serde_json::Value>,
+    /// Cancel notifications previously created by this plugin, instead of creating new ones.
+    #[serde(default)]
+    pub cancel_previous: bool,
+}
+
+fn non_empty<T>(values: &[T]) -> Option<Vec<T>>
+where
+    T: Clone,
+{
+    (!values.is_empty()).then(|| values.to_vec())
+}
+
+fn labels(labels: &BTreeMap<String, String>) -> Option<Vec<Label>> {
+    (!labels.is_empty()).then(|| {
+        labels
+            .iter()
+            .map(|(key, value)| Label {
+                key: key.clone(),
+                value: value.clone(),
+            })
+            .collect()
+    })
+}
+
+impl NotificationRule {
+    fn marker(&self) -> String {
+        format!("{}{}]", MARKER_PREFIX, self.id)
+    }
+
+    fn comment(&self) -> String {
+        let marker = self.marker();
+        match &self.comment {
+            Some(comment) => format!("{}\n{}", comment, marker),
+            None => marker,
+        }
+    }
+
+    fn rule_config(&self) -> NotificationRuleConfig {
+        let plugin_params = PluginParams {
+            plugin_name: self.method.plugin.clone(),
+            parameters: self.method.parameters.clone(),
+        };
+        let conditions = &self.conditions;
+        let contacts = &self.contacts;
+        NotificationRuleConfig {
+            rule_properties: NotificationRuleProperties {
+                description: self.description.clone().unwrap_or_default(),
+                comment: self.comment(),
+                documentation_url: self.documentation_url.clone().unwrap_or_default(),
+                do_not_apply_this_rule: self.disabled.into(),
+                allow_users_to_deactivate: self.allow_users_to_deactivate.into(),
+            },
+            notification_method: ApiNotificationMethod {
+                notify_plugin: if self.method.cancel_previous {
+                    NotifyPlugin::CancelPreviousNotifications { plugin_params }
+                } else {
+                    NotifyPlugin::CreateNotificationWithTheFollowingParameters { plugin_params }
+                },
+            },
+            contact_selection: ContactSelection {
+                all_contacts_of_the_notified_object: contacts.notified_object.into(),
+                all_users: contacts.all_users.into(),
+                all_users_with_an_email_address: contacts.all_users_with_email.into(),
+                the_following_users: non_empty(&contacts.users).into(),
+                members_of_contact_groups: non_empty(&contacts.contact_groups).into(),
+                explicit_email_addresses: non_empty(&contacts.email_addresses).into(),
+            },
+            conditions: NotificationRuleConditions {
+                match_hosts: non_empty(&conditions.hosts).into(),
+                exclude_hosts: non_empty(&conditions.exclude_hosts).into(),
+                match_host_labels: labels(&conditions.host_labels).into(),
+                match_host_groups: non_empty(&conditions.host_groups).into(),
+                match_services: non_empty(&conditions.services).into(),
+                exclude_services: non_empty(&conditions.exclude_services).into(),
+                match_service_labels: labels(&conditions.service_labels).into(),
+                match_service_groups: non_empty(&conditions.service_groups).into(),
+                match_contact_groups: non_empty(&conditions.contact_groups).into(),
+                match_only_during_time_period: conditions.time_period.clone().into(),
+            },
+        }
+    }
+
+    fn is_same_rule(&self, rule_api: &ShowNotificationRuleResponse) -> bool {
+        rule_api
+            .extensions
+            .rule_config
+            .rule_properties
+            .comment
+            .contains(&self.marker())
+    }
+
+    fn needs_update(&self, rule_api: &ShowNotificationRuleResponse) -> bool {
+        let rule_config = self.rule_config();
+        let rule_config_api = &rule_api.extensions.rule_config;
+
+        // Checkmk reports all parameters of the plugin, including those we did not declare, so we
+        // only compare the declared parameters.
+        let plugin_matches = match (
+            &rule_config.notification_method.notify_plugin,
+            &rule_config_api.notification_method.notify_plugin,
+        ) {
+            (
+                NotifyPlugin::CreateNotificationWithTheFollowingParameters { plugin_params },
+                NotifyPlugin::CreateNotificationWithTheFollowingParameters {
+                    plugin_params: plugin_params_api,
+                },
+            )
+            | (
+                NotifyPlugin::CancelPreviousNotifications { plugin_params },
+                NotifyPlugin::CancelPreviousNotifications {
+                    plugin_params: plugin_params_api,
+                },
+            ) => {
+                plugin_params.plugin_name == plugin_params_api.plugin_name
+                    && plugin_params
+                        .parameters
+                        .iter()
+                        .all(|(key, value)| plugin_params_api.parameters.get(key) == Some(value))
+            }
+            _ => false,
+        };
+
+        !plugin_matches
+            || rule_config.rule_properties != rule_config_api.rule_properties
+            || rule_config.contact_selection != rule_config_api.contact_selection
+            || rule_config.conditions != rule_config_api.conditions
+    }
+}
+
+fn replace_all_notification_rules(
+    notification_rules: &[NotificationRule],
+    notification_rules_api: Vec<ShowNotificationRuleResponse>,
+    cmk: &checkmk_client::Client,
+) -> Result<()> {
+    let client = cmk.notification_rules();
+    for rule_api in notification_rules_api {
+        client.delete_notification_rule(&rule_api.id)?;
+    }
+    for notification_rule in notification_rules {
+        // If this fails, the remaining rules stay deleted until the next apply, just like with
+        // rulesets (see the TODO in `Ruleset::replace_all_rules`).
+        client.create_notification_rule(&notification_rule.rule_config())?;
+    }
+    Ok(())
+}
+
+/// Apply the declared notification rules to the site.
+///
+/// Much like rulesets, if any of the notification rules managed by checkmate differs, or they are
+/// not in the declared order, all of them are deleted and (re-)added in the declared order. Since
+/// Checkmk adds new notification rules after all existing ones, managed rules are evaluated after
+/// notification rules that are not managed by checkmate.
+pub fn apply_notification_rules(
+    notification_rules: &[NotificationRule],
+    cmk: &checkmk_client::Client,
+) -> Result<()> {
+    let notification_rules_api = cmk
+        .notification_rules()
+        .list_notification_rules()?
+        .notification_rules
+        .into_iter()
+        .filter(|rule_api| {
+            rule_api
+                .extensions
+                .rule_config
+                .rule_properties
+                .comment
+                .contains(MARKER_PREFIX)
+        })
+        .collect::<Vec<_>>();
+
+    if notification_rules_api.len() != notification_rules.len() {
+        println!(
+            "[NOTIFICATION RULES] different amount of notification rules, replacing all \
+             notification rules"
+        );
+        return replace_all_notification_rules(notification_rules, notification_rules_api, cmk);
+    }
+
+    let any_mismatch = notification_rules
+        .iter()
+        .zip(notification_rules_api.iter())
+        .any(|(rule, rule_api)| !rule.is_same_rule(rule_api) || rule.needs_update(rule_api));
+    if any_mismatch {
+        println!(
+            "[NOTIFICATION RULES] at least one notification rule differs in configuration or \
+             order, replacing all notification rules"
+        );
+        return replace_all_notification_rules(notification_rules, notification_rules_api, cmk);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,232 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use super::SecretRef;
+use crate::{
+    de::DeclaredById,
+    Result,
+};
+use checkmk_client::{
+    models::Secret,
+    passwords::{
+        CreatePasswordRequest,
+        PasswordsApi,
+        ShowPasswordResponse,
+        UpdatePasswordRequest,
+    },
+};
+use hmac::{
+    Hmac,
+    Mac,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use sha2::Sha256;
+
+const PASSWORD_REFERENCE_PREFIX: &str = "{{password:";
+const PASSWORD_REFERENCE_SUFFIX: &str = "}}";
+const STORED_PASSWORD_PREFIX: &str = "('cmk_postprocessed', 'stored_password', ('";
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Password {
+    #[serde(default, skip_serializing)]
+    pub id: String,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documentation_url: Option<String>,
+    /// The owner of the password, which is either `admin` or the name of a contact group.
+    #[serde(default = "default_owner")]
+    pub owner: String,
+    /// The contact groups the password is shared with, or `all`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shared: Vec<String>,
+    pub secret: SecretRef,
+}
+
+fn default_owner() -> String {
+    "admin".to_owned()
+}
+
+impl DeclaredById for Password {
+    fn set_id(&mut self, id: String) {
+        self.id = id;
+    }
+}
+
+impl Password {
+    /// Since Checkmk never reports stored passwords, we keep a fingerprint of the password in the
+    /// comment, which allows us to identify when the password has to be updated.
+    fn marker(&self, secret: &Secret, fingerprint_key: &[u8]) -> String {
+        format!(
+            "[checkmate:password:{}]",
+            fingerprint(fingerprint_key, &self.id, secret)
+        )
+    }
+
+    fn comment(&self, secret: &Secret, fingerprint_key: &[u8]) -> String {
+        let marker = self.marker(secret, fingerprint_key);
+        match &self.comment {
+            Some(comment) => format!("{}\n{}", comment, marker),
+            None => marker,
+        }
+    }
+
+    fn needs_update(&self, password_api: &ShowPasswordResponse, comment: &str) -> bool {
+        let extensions = &password_api.extensions;
+        let mut shared = self.shared.iter().collect::<Vec<_>>();
+        shared.sort_unstable();
+        let mut shared_api = extensions.shared.iter().collect::<Vec<_>>();
+        shared_api.sort_unstable();
+
+        self.title != password_api.title
+            || Some(comment) != extensions.comment.as_deref()
+            || self.documentation_url != extensions.documentation_url
+            || Some(self.owner.as_str()) != extensions.owned_by.as_deref()
+            || shared != shared_api
+    }
+
+    /// Applies the password to the site, using `fingerprint_key` to fingerprint the secret.
+    pub fn apply_to_site(
+        &self,
+        fingerprint_key: &[u8],
+        cmk: &checkmk_client::Client,
+    ) -> Result<()> {
+        let id = &self.id;
+        let secret = self.secret.resolve()?;
+        let comment = self.comment(&secret, fingerprint_key);
+        match cmk.passwords().show_password(id) {
+            Ok((password_api, etag)) if self.needs_update(&password_api, &comment) => {
+                println!("{}: updating existing password", id);
+                cmk.passwords()
+                    .update_password(
+                        id,
+                        etag,
+                        &UpdatePasswordRequest {
+                            title: &self.title,
+                            comment: Some(&comment),
+                            documentation_url: self.documentation_url.as_deref(),
+                            password: &secret,
+                            owner: &self.owner,
+                            shared: &self.shared,
+                        },
+                    )
+                    .map(|_| ())
+                    .map_err(Into::into)
+            }
+            Ok(_) => {
+                println!("{}: password exists with correct attributes", id);
+                Ok(())
+            }
+            Err(error) if error.is_status(404) => {
+                println!("{}: creating missing password", id);
+                cmk.passwords()
+                    .create_password(&CreatePasswordRequest {
+                        ident: id,
+                        title: &self.title,
+                        comment: Some(&comment),
+                        documentation_url: self.documentation_url.as_deref(),
+                        password: &secret,
+                        owner: &self.owner,
+                        shared: &self.shared,
+                    })
+                    .map(|_| ())
+                    .map_err(Into::into)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Returns a fingerprint of the secret of the object with the given ID, to detect when the secret
+/// changed without storing it.
+///
+/// The fingerprint is keyed, so it can't be used to guess the secret by anyone who can read it on
+/// the site, but doesn't know the key.
+pub fn fingerprint(fingerprint_key: &[u8], id: &str, secret: &Secret) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(fingerprint_key).expect("HMAC accepts keys of any length");
+    mac.update(id.as_bytes());
+    mac.update(b":");
+    mac.update(secret.expose().as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .take(8)
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Replace all references to declared passwords, written as `{{password:<id>}}`, with the value
+/// Checkmk uses to refer to entries of the password store.
+///
+/// Returns the expanded value alongside the IDs of the referenced passwords. References to the
+/// password store written in Checkmk's own syntax are left alone, since they may refer to entries
+/// that are not managed by checkmate.
+pub fn expand_password_references(value_raw: &str) -> (String, Vec<String>) {
+    let mut expanded = String::with_capacity(value_raw.len());
+    let mut references = Vec::new();
+    let mut rest = value_raw;
+    while let Some(start) = rest.find(PASSWORD_REFERENCE_PREFIX) {
+        let after_prefix = &rest[start + PASSWORD_REFERENCE_PREFIX.len()..];
+        let Some(end) = after_prefix.find(PASSWORD_REFERENCE_SUFFIX) else {
+            break;
+        };
+        let id = after_prefix[..end].trim();
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(&format!("{}{}', ''))", STORED_PASSWORD_PREFIX, id));
+        references.push(id.to_owned());
+        rest = &after_prefix[end + PASSWORD_REFERENCE_SUFFIX.len()..];
+    }
+    expanded.push_str(rest);
+    (expanded, references)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_password_references() {
+        let (expanded, references) = expand_password_references(
+            "{'auth': ('user', {{password:first}}), 'other': {{password:second}}}",
+        );
+        assert_eq!(
+            expanded,
+            "{'auth': ('user', ('cmk_postprocessed', 'stored_password', ('first', ''))), 'other': \
+             ('cmk_postprocessed', 'stored_password', ('second', ''))}"
+        );
+        assert_eq!(references, ["first", "second"]);
+    }
+
+    #[test]
+    fn leaves_references_in_checkmk_syntax_alone() {
+        let value_raw = "('cmk_postprocessed', 'stored_password', ('manual', ''))";
+        let (expanded, references) = expand_password_references(value_raw);
+        assert_eq!(expanded, value_raw);
+        assert!(references.is_empty());
+    }
+
+    #[test]
+    fn leaves_unterminated_references_alone() {
+        let (expanded, references) = expand_password_references("{'password': '{{password:x'}");
+        assert_eq!(expanded, "{'password': '{{password:x'}");
+        assert!(references.is_empty());
+    }
+}
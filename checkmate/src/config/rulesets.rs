@@ -15,28 +15,40 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    config::Folder,
+    config::{
+        expand_password_references,
+        Folder,
+    },
     Result,
 };
 use checkmk_client::rules::{
     CreateRuleRequest,
+    HostNameCondition,
+    HostNameConditionOperator,
     RuleConditions,
     RuleProperties,
     RulesApi,
     ShowRuleResponse,
 };
-use serde::Deserialize;
+use serde::{
+    Deserialize,
+    Serialize,
+};
 use std::rc::{
     Rc,
     Weak,
 };
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Ruleset {
-    #[serde(default)]
+    #[serde(default, skip_serializing)]
     pub name: String,
     pub rules: Vec<Rule>,
-    #[serde(default, deserialize_with = "crate::de::deserialize_to_empty_weak")]
+    #[serde(
+        default,
+        deserialize_with = "crate::de::deserialize_to_empty_weak",
+        skip_serializing
+    )]
     pub folder: Weak<Folder>,
 }
 
@@ -100,7 +112,14 @@ impl Ruleset {
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+const HOST_RULE_ID_PREFIX: &str = "host:";
+
+/// The beginning of the marker of all rules in the given ruleset that were declared on a host.
+pub fn host_rule_marker_prefix(ruleset: &str) -> String {
+    format!("[checkmate:{}:{}", ruleset, HOST_RULE_ID_PREFIX)
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Rule {
     /// The user's unique identifier of the rule.
     ///
@@ -112,8 +131,15 @@ pub struct Rule {
     #[serde(default)]
     pub conditions: RuleConditions,
     pub value_raw: String,
-    #[serde(default, deserialize_with = "crate::de::deserialize_to_empty_weak")]
+    #[serde(
+        default,
+        deserialize_with = "crate::de::deserialize_to_empty_weak",
+        skip_serializing
+    )]
     pub ruleset: Weak<Ruleset>,
+    /// The IDs of the declared passwords the rule refers to as `{{password:<id>}}`.
+    #[serde(skip)]
+    pub password_references: Vec<String>,
 }
 
 impl Rule {
@@ -139,6 +165,26 @@ impl Rule {
         format!("[checkmate:{}:{}]", ruleset, self.custom_id)
     }
 
+    /// Replace the references to declared passwords in the rule's value, remembering which
+    /// passwords it refers to.
+    pub fn expand_password_references(&mut self) {
+        let (value_raw, references) = expand_password_references(&self.value_raw);
+        self.value_raw = value_raw;
+        self.password_references = references;
+    }
+
+    /// Turn a rule declared on a host into a rule of the host's folder that only applies to the
+    /// host. The host is part of the rule's ID, and therefore of its marker.
+    pub fn scope_to_host(&mut self, ruleset: &str, host_name: &str) {
+        self.custom_id = format!("{}{}:{}", HOST_RULE_ID_PREFIX, host_name, self.custom_id);
+        self.conditions.host_name = Some(HostNameCondition {
+            match_on: vec![host_name.to_owned()],
+            operator: Some(HostNameConditionOperator::OneOf),
+        });
+        self.expand_password_references();
+        self.normalize_properties(ruleset);
+    }
+
     fn is_same_rule(&self, api_rule: &ShowRuleResponse) -> bool {
         api_rule
             .extensions
@@ -0,0 +1,47 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::Result;
+use checkmk_client::models::Secret;
+use color_eyre::eyre::WrapErr;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use std::path::PathBuf;
+
+/// A reference to a secret that is kept outside of the configuration file.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum SecretRef {
+    /// Read the secret from the given environment variable.
+    Env { env: String },
+    /// Read the secret from the given file, ignoring trailing newlines.
+    File { file: PathBuf },
+}
+
+impl SecretRef {
+    pub fn resolve(&self) -> Result<Secret> {
+        match self {
+            Self::Env { env } => std::env::var(env).map(Secret::new).wrap_err_with(|| {
+                format!("Failed to read secret from environment variable {}", env)
+            }),
+            Self::File { file } => std::fs::read_to_string(file)
+                .map(|secret| Secret::new(secret.trim_end_matches(['\r', '\n'])))
+                .wrap_err_with(|| format!("Failed to read secret from file {}", file.display())),
+        }
+    }
+}
@@ -0,0 +1,88 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    de::DeclaredById,
+    Result,
+};
+use checkmk_client::site_connections::{
+    BasicSettings,
+    ConfigurationConnection,
+    SiteConfig,
+    SiteConnectionsApi,
+    StatusConnection,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+/// A remote site connected to the site checkmate applies the configuration to.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Site {
+    #[serde(default, skip_serializing)]
+    pub id: String,
+    pub alias: String,
+    pub status_connection: StatusConnection,
+    #[serde(default)]
+    pub configuration_connection: ConfigurationConnection,
+}
+
+impl DeclaredById for Site {
+    fn set_id(&mut self, id: String) {
+        if self.status_connection.url_prefix.is_empty() {
+            self.status_connection.url_prefix = format!("/{}/", id);
+        }
+        self.id = id;
+    }
+}
+
+impl Site {
+    fn site_config(&self) -> SiteConfig {
+        SiteConfig {
+            basic_settings: BasicSettings {
+                site_id: self.id.clone(),
+                alias: self.alias.clone(),
+            },
+            status_connection: self.status_connection.clone(),
+            configuration_connection: self.configuration_connection.clone(),
+        }
+    }
+
+    pub fn apply_to_site(&self, cmk: &checkmk_client::Client) -> Result<()> {
+        let id = &self.id;
+        let site_config = self.site_config();
+        match cmk.site_connections().show_site_connection(id) {
+            Ok(site_connection_api) if site_config != site_connection_api.extensions => {
+                println!("{}: updating existing site connection", id);
+                cmk.site_connections()
+                    .update_site_connection(id, &site_config)?;
+                Ok(())
+            }
+            Ok(_) => {
+                println!("{}: site connection exists with correct attributes", id);
+                Ok(())
+            }
+            Err(error) if error.is_status(404) => {
+                println!("{}: creating missing site connection", id);
+                cmk.site_connections()
+                    .create_site_connection(&site_config)?;
+                Ok(())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
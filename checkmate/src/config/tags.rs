@@ -0,0 +1,219 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    de::DeclaredById,
+    Result,
+};
+use checkmk_client::host_tags::{
+    AuxTagsApi,
+    CreateAuxTagRequest,
+    CreateHostTagGroupRequest,
+    HostTag,
+    HostTagGroupsApi,
+    ShowAuxTagResponse,
+    ShowHostTagGroupResponse,
+    UpdateAuxTagRequest,
+    UpdateHostTagGroupRequest,
+};
+use color_eyre::eyre::WrapErr;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TagGroup {
+    #[serde(default, skip_serializing)]
+    pub id: String,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub help: Option<String>,
+    pub tags: Vec<HostTag>,
+}
+
+impl DeclaredById for TagGroup {
+    fn set_id(&mut self, id: String) {
+        self.id = id;
+    }
+}
+
+impl TagGroup {
+    pub fn tag_ids(&self) -> impl Iterator<Item = &str> {
+        self.tags.iter().map(|tag| tag.id.as_str())
+    }
+
+    fn needs_update(&self, tag_group_api: &ShowHostTagGroupResponse) -> bool {
+        self.title != tag_group_api.title
+            || self.topic != tag_group_api.extensions.topic
+            || self.help != tag_group_api.extensions.help
+            || self.tags != tag_group_api.extensions.tags
+    }
+
+    fn removed_tags<'a>(&self, tag_group_api: &'a ShowHostTagGroupResponse) -> Vec<&'a str> {
+        tag_group_api
+            .extensions
+            .tags
+            .iter()
+            .map(|tag| tag.id.as_str())
+            .filter(|tag_id| !self.tag_ids().any(|id| id == *tag_id))
+            .collect()
+    }
+
+    pub fn apply_to_site(&self, cmk: &checkmk_client::Client) -> Result<()> {
+        let id = &self.id;
+        match cmk.host_tag_groups().show_host_tag_group(id) {
+            Ok((tag_group_api, etag)) if self.needs_update(&tag_group_api) => {
+                let removed_tags = self.removed_tags(&tag_group_api);
+                if !removed_tags.is_empty() {
+                    println!(
+                        "{}: removing tags {} from tag group",
+                        id,
+                        removed_tags.join(", ")
+                    );
+                }
+                println!("{}: updating existing tag group", id);
+                match cmk
+                    .host_tag_groups()
+                    .update_host_tag_group(id, etag, &self.into())
+                {
+                    Ok(_) => Ok(()),
+                    // We never ask Checkmk to repair hosts, folders or rules that still use the
+                    // removed tags, so it refuses to remove tags that are still in use.
+                    Err(error) if !removed_tags.is_empty() && error.is_status(401) => Err(error)
+                        .wrap_err_with(|| {
+                            format!(
+                                "Tags {} of tag group {} are removed, but still used by hosts, \
+                                 folders or rules on the site",
+                                removed_tags.join(", "),
+                                id
+                            )
+                        }),
+                    Err(error) => Err(error.into()),
+                }
+            }
+            Ok(_) => {
+                println!("{}: tag group exists with correct attributes", id);
+                Ok(())
+            }
+            Err(error) if error.is_status(404) => {
+                println!("{}: creating missing tag group", id);
+                cmk.host_tag_groups()
+                    .create_host_tag_group(&self.into())
+                    .map(|_| ())
+                    .map_err(Into::into)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+impl<'a> From<&'a TagGroup> for CreateHostTagGroupRequest<'a> {
+    fn from(tag_group: &'a TagGroup) -> Self {
+        Self {
+            ident: &tag_group.id,
+            title: &tag_group.title,
+            topic: &tag_group.topic,
+            help: &tag_group.help,
+            tags: tag_group.tags.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl<'a> From<&'a TagGroup> for UpdateHostTagGroupRequest<'a> {
+    fn from(tag_group: &'a TagGroup) -> Self {
+        Self {
+            title: &tag_group.title,
+            topic: &tag_group.topic,
+            help: &tag_group.help,
+            tags: tag_group.tags.iter().map(Into::into).collect(),
+            repair: false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AuxTag {
+    #[serde(default, skip_serializing)]
+    pub id: String,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub help: Option<String>,
+}
+
+impl DeclaredById for AuxTag {
+    fn set_id(&mut self, id: String) {
+        self.id = id;
+    }
+}
+
+impl AuxTag {
+    fn needs_update(&self, aux_tag_api: &ShowAuxTagResponse) -> bool {
+        self.title != aux_tag_api.title
+            || self.topic != aux_tag_api.extensions.topic
+            || self.help != aux_tag_api.extensions.help
+    }
+
+    pub fn apply_to_site(&self, cmk: &checkmk_client::Client) -> Result<()> {
+        let id = &self.id;
+        match cmk.aux_tags().show_aux_tag(id) {
+            Ok((aux_tag_api, etag)) if self.needs_update(&aux_tag_api) => {
+                println!("{}: updating existing aux tag", id);
+                cmk.aux_tags()
+                    .update_aux_tag(id, etag, &self.into())
+                    .map(|_| ())
+                    .map_err(Into::into)
+            }
+            Ok(_) => {
+                println!("{}: aux tag exists with correct attributes", id);
+                Ok(())
+            }
+            Err(error) if error.is_status(404) => {
+                println!("{}: creating missing aux tag", id);
+                cmk.aux_tags()
+                    .create_aux_tag(&self.into())
+                    .map(|_| ())
+                    .map_err(Into::into)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+impl<'a> From<&'a AuxTag> for CreateAuxTagRequest<'a> {
+    fn from(aux_tag: &'a AuxTag) -> Self {
+        Self {
+            aux_tag_id: &aux_tag.id,
+            title: &aux_tag.title,
+            topic: &aux_tag.topic,
+            help: &aux_tag.help,
+        }
+    }
+}
+
+impl<'a> From<&'a AuxTag> for UpdateAuxTagRequest<'a> {
+    fn from(aux_tag: &'a AuxTag) -> Self {
+        Self {
+            title: &aux_tag.title,
+            topic: &aux_tag.topic,
+            help: &aux_tag.help,
+        }
+    }
+}
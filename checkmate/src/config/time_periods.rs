@@ -0,0 +1,225 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    de::DeclaredById,
+    Result,
+};
+use checkmk_client::time_periods::{
+    ActiveTimeRange,
+    CreateTimePeriodRequest,
+    ShowTimePeriodResponse,
+    TimePeriodException,
+    TimePeriodsApi,
+    TimeRange,
+    UpdateTimePeriodRequest,
+    Weekday,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use std::collections::BTreeMap;
+
+/// The time period that is built into Checkmk and always active.
+pub const BUILTIN_TIME_PERIOD: &str = "24X7";
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TimePeriod {
+    #[serde(default, skip_serializing)]
+    pub name: String,
+    pub alias: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub active_time_ranges: Vec<ActiveTimeRange>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exceptions: Vec<TimePeriodException>,
+    /// The names of the declared time periods that are excluded from this time period.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+}
+
+impl DeclaredById for TimePeriod {
+    fn set_id(&mut self, id: String) {
+        self.name = id;
+    }
+}
+
+/// Checkmk may report times with seconds, whereas they are usually declared without.
+fn normalize_time_ranges(time_ranges: &[TimeRange]) -> Vec<TimeRange> {
+    fn normalize_time(time: &str) -> String {
+        time.strip_suffix(":00")
+            .filter(|time| time.len() == 5)
+            .unwrap_or(time)
+            .to_owned()
+    }
+
+    let mut time_ranges = time_ranges
+        .iter()
+        .map(|time_range| TimeRange {
+            start: normalize_time(&time_range.start),
+            end: normalize_time(&time_range.end),
+        })
+        .collect::<Vec<_>>();
+    time_ranges.sort();
+    time_ranges
+}
+
+/// Expands `all` into the individual days, and merges all time ranges of each day, so that the
+/// active time ranges can be compared to what Checkmk reports.
+fn normalize_active_time_ranges(
+    active_time_ranges: &[ActiveTimeRange],
+) -> BTreeMap<Weekday, Vec<TimeRange>> {
+    let mut days: BTreeMap<Weekday, Vec<TimeRange>> = BTreeMap::new();
+    for active_time_range in active_time_ranges {
+        let affected_days = match active_time_range.day {
+            Weekday::All => Weekday::DAYS.to_vec(),
+            day => vec![day],
+        };
+        for day in affected_days {
+            days.entry(day)
+                .or_default()
+                .extend(active_time_range.time_ranges.iter().cloned());
+        }
+    }
+    days.into_iter()
+        .filter(|(_, time_ranges)| !time_ranges.is_empty())
+        .map(|(day, time_ranges)| (day, normalize_time_ranges(&time_ranges)))
+        .collect()
+}
+
+fn normalize_exceptions(exceptions: &[TimePeriodException]) -> BTreeMap<&str, Vec<TimeRange>> {
+    exceptions
+        .iter()
+        .map(|exception| {
+            (
+                exception.date.as_str(),
+                normalize_time_ranges(&exception.time_ranges),
+            )
+        })
+        .collect()
+}
+
+impl TimePeriod {
+    /// Checkmk refers to excluded time periods by their alias, not their name.
+    fn exclude_aliases<'a>(&self, time_periods: &'a BTreeMap<String, TimePeriod>) -> Vec<&'a str> {
+        self.exclude
+            .iter()
+            .filter_map(|name| time_periods.get(name))
+            .map(|time_period| time_period.alias.as_str())
+            .collect()
+    }
+
+    fn needs_update(
+        &self,
+        time_period_api: &ShowTimePeriodResponse,
+        exclude_aliases: &[&str],
+    ) -> bool {
+        let extensions = &time_period_api.extensions;
+        let mut exclude_aliases = exclude_aliases.to_vec();
+        exclude_aliases.sort_unstable();
+        let mut exclude_aliases_api = extensions
+            .exclude
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        exclude_aliases_api.sort_unstable();
+
+        self.alias != extensions.alias
+            || normalize_active_time_ranges(&self.active_time_ranges)
+                != normalize_active_time_ranges(&extensions.active_time_ranges)
+            || normalize_exceptions(&self.exceptions)
+                != normalize_exceptions(&extensions.exceptions)
+            || exclude_aliases != exclude_aliases_api
+    }
+
+    pub fn apply_to_site(
+        &self,
+        time_periods: &BTreeMap<String, TimePeriod>,
+        cmk: &checkmk_client::Client,
+    ) -> Result<()> {
+        let name = &self.name;
+        let exclude = self.exclude_aliases(time_periods);
+        match cmk.time_periods().show_time_period(name) {
+            Ok((time_period_api, etag)) if self.needs_update(&time_period_api, &exclude) => {
+                println!("{}: updating existing time period", name);
+                cmk.time_periods()
+                    .update_time_period(
+                        name,
+                        etag,
+                        &UpdateTimePeriodRequest {
+                            alias: &self.alias,
+                            active_time_ranges: &self.active_time_ranges,
+                            exceptions: &self.exceptions,
+                            exclude,
+                        },
+                    )
+                    .map(|_| ())
+                    .map_err(Into::into)
+            }
+            Ok(_) => {
+                println!("{}: time period exists with correct attributes", name);
+                Ok(())
+            }
+            Err(error) if error.is_status(404) => {
+                println!("{}: creating missing time period", name);
+                cmk.time_periods()
+                    .create_time_period(&CreateTimePeriodRequest {
+                        name,
+                        alias: &self.alias,
+                        active_time_ranges: &self.active_time_ranges,
+                        exceptions: &self.exceptions,
+                        exclude,
+                    })
+                    .map(|_| ())
+                    .map_err(Into::into)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Orders the time periods such that every time period comes after the time periods it excludes,
+/// since those have to exist before they can be excluded.
+///
+/// Exclusions must not be cyclic, which is verified when loading the configuration.
+pub fn ordered_time_periods(time_periods: &BTreeMap<String, TimePeriod>) -> Vec<&TimePeriod> {
+    fn visit<'a>(
+        time_period: &'a TimePeriod,
+        time_periods: &'a BTreeMap<String, TimePeriod>,
+        ordered: &mut Vec<&'a TimePeriod>,
+    ) {
+        if ordered
+            .iter()
+            .any(|ordered| ordered.name == time_period.name)
+        {
+            return;
+        }
+        for excluded in time_period
+            .exclude
+            .iter()
+            .filter_map(|name| time_periods.get(name))
+        {
+            visit(excluded, time_periods, ordered);
+        }
+        ordered.push(time_period);
+    }
+
+    let mut ordered = Vec::with_capacity(time_periods.len());
+    for time_period in time_periods.values() {
+        visit(time_period, time_periods, &mut ordered);
+    }
+    ordered
+}
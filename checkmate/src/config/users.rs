@@ -0,0 +1,309 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{
+    passwords,
+    SecretRef,
+};
+use crate::{
+    de::DeclaredById,
+    Result,
+};
+use checkmk_client::{
+    models::ExtraAttributes,
+    users::{
+        AuthOption,
+        AuthType,
+        ContactOptions,
+        CreateUserRequest,
+        DisableNotifications,
+        ShowUserResponse,
+        UpdateUserRequest,
+        UsersApi,
+    },
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct User {
+    #[serde(default, skip_serializing)]
+    pub username: String,
+    pub fullname: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pager_address: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contact_groups: Vec<String>,
+    #[serde(default)]
+    pub notifications: UserNotifications,
+    /// How the user authenticates. Users without authentication can't log in, but can still be
+    /// notified.
+    ///
+    /// Since Checkmk never reports secrets, a fingerprint of the secret is stored in the custom
+    /// user attribute `checkmate_auth_fingerprint`, which has to be defined on the site.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth: Option<UserAuth>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct UserNotifications {
+    #[serde(default)]
+    pub disabled: bool,
+    /// Whether the user receives notifications that no other contact matched.
+    #[serde(default)]
+    pub fallback_contact: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UserAuth {
+    #[serde(rename = "type")]
+    pub auth_type: AuthType,
+    pub secret: SecretRef,
+}
+
+/// What happens to users on the site that are not declared.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UserRemovalPolicy {
+    /// Leave users that are not declared untouched.
+    #[default]
+    Keep,
+    /// Prevent users that are not declared from logging in.
+    Deactivate,
+    /// Delete users that are not declared.
+    Delete,
+}
+
+impl DeclaredById for User {
+    fn set_id(&mut self, id: String) {
+        self.username = id;
+    }
+}
+
+/// The custom user attribute storing the fingerprint of the declared secret of a user.
+const AUTH_FINGERPRINT_ATTRIBUTE: &str = "checkmate_auth_fingerprint";
+
+impl UserAuth {
+    fn auth_option(&self) -> Result<AuthOption> {
+        let secret = self.secret.resolve()?;
+        Ok(match self.auth_type {
+            AuthType::Password => AuthOption::Password { password: secret },
+            AuthType::Automation => AuthOption::Automation { secret },
+        })
+    }
+}
+
+/// The authentication of a user as it is sent to the site, alongside the fingerprint of its secret.
+struct ResolvedAuth {
+    auth_option: AuthOption,
+    fingerprint: String,
+}
+
+impl ResolvedAuth {
+    fn extra_attributes(&self) -> ExtraAttributes {
+        [(AUTH_FINGERPRINT_ATTRIBUTE, self.fingerprint.clone().into())]
+            .into_iter()
+            .collect()
+    }
+}
+
+impl User {
+    fn sorted(values: &[String]) -> Vec<&str> {
+        let mut values = values.iter().map(String::as_str).collect::<Vec<_>>();
+        values.sort_unstable();
+        values
+    }
+
+    fn contact_options(&self) -> ContactOptions {
+        ContactOptions {
+            email: self.email.clone().unwrap_or_default(),
+            fallback_contact: Some(self.notifications.fallback_contact),
+        }
+    }
+
+    fn disable_notifications(&self) -> DisableNotifications {
+        DisableNotifications {
+            disable: Some(self.notifications.disabled),
+        }
+    }
+
+    fn resolve_auth(&self, fingerprint_key: &[u8]) -> Result<Option<ResolvedAuth>> {
+        let Some(auth) = &self.auth else {
+            return Ok(None);
+        };
+        let auth_option = auth.auth_option()?;
+        let secret = match &auth_option {
+            AuthOption::Password { password } => password,
+            AuthOption::Automation { secret } => secret,
+        };
+        let fingerprint = passwords::fingerprint(fingerprint_key, &self.username, secret);
+        Ok(Some(ResolvedAuth {
+            auth_option,
+            fingerprint,
+        }))
+    }
+
+    /// Returns whether the user authenticates differently than declared, i.e. the type of
+    /// authentication or the fingerprint of the secret differ.
+    fn needs_auth_update(user_api: &ShowUserResponse, auth: Option<&ResolvedAuth>) -> bool {
+        let Some(auth) = auth else {
+            return false;
+        };
+        let extensions = &user_api.extensions;
+        let auth_type_api = extensions
+            .auth_option
+            .as_ref()
+            .and_then(|auth_option| auth_option.auth_type);
+        Some(auth.auth_option.auth_type()) != auth_type_api
+            || extensions
+                .extra_attributes
+                .get(AUTH_FINGERPRINT_ATTRIBUTE)
+                .and_then(|fingerprint| fingerprint.as_str())
+                != Some(auth.fingerprint.as_str())
+    }
+
+    fn needs_update(&self, user_api: &ShowUserResponse, auth: Option<&ResolvedAuth>) -> bool {
+        let extensions = &user_api.extensions;
+        let contact_options = extensions.contact_options.clone().unwrap_or_default();
+        self.fullname != extensions.fullname
+            || extensions.disable_login
+            || self.email.as_deref().unwrap_or_default() != contact_options.email
+            || self.notifications.fallback_contact
+                != contact_options.fallback_contact.unwrap_or(false)
+            || self.pager_address.as_deref().unwrap_or_default()
+                != extensions.pager_address.as_deref().unwrap_or_default()
+            || Self::sorted(&self.roles) != Self::sorted(&extensions.roles)
+            || Self::sorted(&self.contact_groups) != Self::sorted(&extensions.contact_groups)
+            || self.notifications.disabled
+                != extensions
+                    .disable_notifications
+                    .as_ref()
+                    .and_then(|disable_notifications| disable_notifications.disable)
+                    .unwrap_or(false)
+            || Self::needs_auth_update(user_api, auth)
+    }
+
+    /// Applies the user to the site, using `fingerprint_key` to fingerprint the declared secret.
+    pub fn apply_to_site(
+        &self,
+        fingerprint_key: &[u8],
+        cmk: &checkmk_client::Client,
+    ) -> Result<()> {
+        let username = &self.username;
+        let auth = self.resolve_auth(fingerprint_key)?;
+        match cmk.users().show_user(username) {
+            Ok((user_api, etag)) if self.needs_update(&user_api, auth.as_ref()) => {
+                println!("{}: updating existing user", username);
+                // Secrets are only sent if they changed, since every update is a pending change.
+                let auth = auth.filter(|auth| Self::needs_auth_update(&user_api, Some(auth)));
+                let update = UpdateUserRequest {
+                    fullname: Some(&self.fullname),
+                    auth_option: auth.as_ref().map(|auth| auth.auth_option.clone()),
+                    disable_login: Some(false),
+                    contact_options: Some(self.contact_options()),
+                    pager_address: Some(self.pager_address.as_deref().unwrap_or_default()),
+                    roles: Some(&self.roles),
+                    contact_groups: Some(&self.contact_groups),
+                    disable_notifications: Some(self.disable_notifications()),
+                    extra_attributes: auth
+                        .as_ref()
+                        .map(ResolvedAuth::extra_attributes)
+                        .unwrap_or_default(),
+                };
+                cmk.users()
+                    .update_user(username, etag, &update)
+                    .map(|_| ())
+                    .map_err(Into::into)
+            }
+            Ok(_) => {
+                println!("{}: user exists with correct attributes", username);
+                Ok(())
+            }
+            Err(error) if error.is_status(404) => {
+                println!("{}: creating missing user", username);
+                let create = CreateUserRequest {
+                    username,
+                    fullname: &self.fullname,
+                    auth_option: auth.as_ref().map(|auth| auth.auth_option.clone()),
+                    disable_login: false,
+                    contact_options: Some(self.contact_options()),
+                    pager_address: self.pager_address.as_deref(),
+                    roles: &self.roles,
+                    contact_groups: &self.contact_groups,
+                    disable_notifications: Some(self.disable_notifications()),
+                    extra_attributes: auth
+                        .as_ref()
+                        .map(ResolvedAuth::extra_attributes)
+                        .unwrap_or_default(),
+                };
+                cmk.users()
+                    .create_user(&create)
+                    .map(|_| ())
+                    .map_err(Into::into)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Deactivate or delete all users on the site which are not declared, according to the policy.
+///
+/// Users listed in `unmanaged_users` are never touched.
+pub fn remove_orphaned_users(
+    users: &BTreeMap<String, User>,
+    policy: UserRemovalPolicy,
+    unmanaged_users: &[String],
+    cmk: &checkmk_client::Client,
+) -> Result<()> {
+    if policy == UserRemovalPolicy::Keep {
+        return Ok(());
+    }
+
+    for user_api in cmk.users().list_users()?.users {
+        let username = &user_api.id;
+        if users.contains_key(username) || unmanaged_users.contains(username) {
+            continue;
+        }
+        match policy {
+            UserRemovalPolicy::Keep => {}
+            UserRemovalPolicy::Deactivate if user_api.extensions.disable_login => {}
+            UserRemovalPolicy::Deactivate => {
+                println!("{}: deactivating orphaned user", username);
+                let (_, etag) = cmk.users().show_user(username)?;
+                cmk.users().update_user(
+                    username,
+                    etag,
+                    &UpdateUserRequest {
+                        disable_login: Some(true),
+                        ..Default::default()
+                    },
+                )?;
+            }
+            UserRemovalPolicy::Delete => {
+                println!("{}: deleting orphaned user", username);
+                cmk.users().delete_user(username)?;
+            }
+        }
+    }
+    Ok(())
+}
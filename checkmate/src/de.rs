@@ -19,7 +19,10 @@ use serde::{
     Deserializer,
 };
 use std::{
-    collections::HashMap,
+    collections::{
+        BTreeMap,
+        HashMap,
+    },
     rc::{
         Rc,
         Weak,
@@ -47,3 +50,23 @@ where
 {
     Ok(Weak::new())
 }
+
+/// Objects that are declared in a map, where the key of the map is the ID of the object.
+pub trait DeclaredById {
+    fn set_id(&mut self, id: String);
+}
+
+pub fn deserialize_map_with_ids<'de, D, V>(deserializer: D) -> Result<BTreeMap<String, V>, D::Error>
+where
+    D: Deserializer<'de>,
+    V: Deserialize<'de> + DeclaredById,
+{
+    let map: BTreeMap<String, V> = BTreeMap::deserialize(deserializer)?;
+    Ok(map
+        .into_iter()
+        .map(|(id, mut value)| {
+            value.set_id(id.clone());
+            (id, value)
+        })
+        .collect())
+}
@@ -17,14 +17,35 @@
 mod cli;
 mod config;
 mod de;
+mod ser;
+mod summary;
 
-use crate::config::{
-    DeclarativeConfig,
-    Folder,
+use crate::{
+    config::{
+        apply_notification_rules,
+        ordered_time_periods,
+        prune_groups,
+        remove_orphaned_users,
+        DeclarativeConfig,
+        Folder,
+    },
+    summary::Summary,
+};
+use checkmk_client::{
+    changes::ChangesApi,
+    host_tags::{
+        AuxTagsApi,
+        HostTagGroupsApi,
+    },
+    redact::Redacted,
+    site_connections::SiteConnectionsApi,
 };
-use checkmk_client::changes::ChangesApi;
 use clap::Parser;
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{
+    Result,
+    WrapErr,
+};
+use std::time::Duration;
 
 fn main() -> Result<()> {
     color_eyre::install()?;
@@ -32,7 +53,8 @@ fn main() -> Result<()> {
 
     match cli.command {
         cli::Commands::Lint(args) => lint(args),
-        cli::Commands::Apply(args) => apply(args),
+        cli::Commands::Render(args) => render(args),
+        cli::Commands::Apply(args) => apply(*args),
     }
 }
 
@@ -40,6 +62,9 @@ fn lint(args: cli::Lint) -> Result<()> {
     // Loading the config already validates that the file is valid YAML, conforms to our schema, and
     // that certain preconditions (like no duplicate hosts) are fulfilled.
     let config = DeclarativeConfig::load_from_file(&args.config_file)?;
+    if let Some(site) = &args.site {
+        config.verify_sites(&[site])?;
+    }
     println!("Provided config file is valid.");
 
     if args.print_config {
@@ -49,36 +74,279 @@ fn lint(args: cli::Lint) -> Result<()> {
     Ok(())
 }
 
-fn apply(args: cli::Apply) -> Result<()> {
-    let client =
-        checkmk_client::Client::new(&args.server_url, &args.site, &args.username, &args.secret)?;
+fn render(args: cli::Render) -> Result<()> {
     let config = DeclarativeConfig::load_from_file(&args.config_file)?;
 
-    process_folders(client, config)?;
+    let rendered = if args.show_secrets {
+        serialize(args.format, &config)?
+    } else {
+        serialize(args.format, &Redacted(&config))?
+    };
+    print!("{}", rendered);
+
     Ok(())
 }
 
-fn process_folders(client: checkmk_client::Client, config: DeclarativeConfig) -> Result<()> {
-    let root_folder = config.folders.root_folder;
-    apply_folders(&client, &root_folder)?;
+fn serialize<T: serde::Serialize>(format: cli::RenderFormat, value: &T) -> Result<String> {
+    Ok(match format {
+        cli::RenderFormat::Yaml => serde_yaml::to_string(value)?,
+        cli::RenderFormat::Json => serde_json::to_string_pretty(value)? + "\n",
+    })
+}
+
+fn apply(args: cli::Apply) -> Result<()> {
+    let client = build_client(&args)?;
+    let config = DeclarativeConfig::load_from_file(&args.config_file)?;
+
+    process_sites(&client, &config, &args.site)?;
+    process_tags(&client, &config)?;
+    process_groups(&client, &config)?;
+    process_time_periods(&client, &config)?;
+    let fingerprint_key = fingerprint_key(&args, &config)?;
+    process_users(&client, &config, &args.username, fingerprint_key)?;
+    process_passwords(&client, &config, fingerprint_key)?;
+    apply_notification_rules(&config.notification_rules, &client)?;
+    let mut summary = Summary::default();
+    process_folders(&client, &config, &mut summary)?;
+    process_dcd_connections(&client, &config)?;
+    config.bi.apply_to_site(&client)?;
+    prune_orphaned_groups(&client, &config)?;
     apply_pending_changes(&client)?;
+    summary.print();
     Ok(())
 }
 
-fn apply_folders(client: &checkmk_client::Client, folder: &Folder) -> Result<()> {
-    folder.apply_to_site(client)?;
+fn build_client(args: &cli::Apply) -> Result<checkmk_client::Client> {
+    let mut builder =
+        checkmk_client::Client::builder(&args.server_url, &args.site, &args.username, &args.secret)
+            .danger_accept_invalid_certs(args.insecure)
+            .retry_policy(checkmk_client::RetryPolicy {
+                max_attempts: args.max_attempts,
+                ..Default::default()
+            });
+    if let Some(connect_timeout) = args.connect_timeout {
+        builder = builder.connect_timeout(Duration::from_secs(connect_timeout));
+    }
+    if let Some(request_timeout) = args.request_timeout {
+        builder = builder.request_timeout(Duration::from_secs(request_timeout));
+    }
+    for ca_bundle in &args.ca_bundle {
+        builder = builder.add_ca_bundle(
+            std::fs::read(ca_bundle)
+                .wrap_err_with(|| format!("Failed to read CA bundle {}", ca_bundle.display()))?,
+        );
+    }
+    if let Some(proxy) = &args.proxy {
+        builder = builder.proxy(proxy);
+    }
+    if args.no_proxy {
+        builder = builder.no_proxy();
+    }
+    if let Some(client_identity) = &args.client_identity {
+        builder = builder.identity(std::fs::read(client_identity).wrap_err_with(|| {
+            format!(
+                "Failed to read client identity {}",
+                client_identity.display()
+            )
+        })?);
+    }
+    if let Some(api_base_path) = &args.api_base_path {
+        builder = builder.api_base_path(api_base_path);
+    }
+    Ok(builder.build()?)
+}
+
+/// The key to fingerprint declared secrets with, which is only required if there are any.
+fn fingerprint_key<'a>(args: &'a cli::Apply, config: &DeclarativeConfig) -> Result<&'a [u8]> {
+    match &args.fingerprint_key {
+        Some(fingerprint_key) => Ok(fingerprint_key.as_bytes()),
+        None if config.declares_secrets() => color_eyre::eyre::bail!(
+            "A fingerprint key has to be provided through --fingerprint-key or \
+             CHECKMATE_FINGERPRINT_KEY, since the configuration declares secrets"
+        ),
+        None => Ok(&[]),
+    }
+}
+
+/// How often an object is applied before giving up, if it keeps being modified on the site while
+/// it is being applied.
+///
+/// This is independent of `--max-attempts`, which only covers requests that failed transiently.
+/// A conflict means someone else is changing the same object at the same time, which a few fresh
+/// attempts resolve, unless the other party keeps changing it, in which case we give up.
+const MAX_CONFLICT_ATTEMPTS: u32 = 3;
+
+/// Apply an object again if it was modified on the site while it was being applied, up to
+/// [`MAX_CONFLICT_ATTEMPTS`] times.
+fn retry_on_conflict<T>(operation: impl FnMut() -> Result<T>) -> Result<T> {
+    checkmk_client::retry_on_precondition_failed(MAX_CONFLICT_ATTEMPTS, operation)
+}
+
+fn process_sites(
+    client: &checkmk_client::Client,
+    config: &DeclarativeConfig,
+    own_site: &str,
+) -> Result<()> {
+    // Remote sites have to be connected before folders and hosts can be assigned to them.
+    for site in config.sites.values() {
+        site.apply_to_site(client)?;
+    }
+
+    let site_connections = client.site_connections().list_site_connections()?;
+    let existing_sites = site_connections
+        .site_connections
+        .iter()
+        .map(|site_connection| site_connection.id.as_str())
+        .chain([own_site])
+        .collect::<Vec<_>>();
+    config.verify_sites(&existing_sites)
+}
+
+fn process_tags(client: &checkmk_client::Client, config: &DeclarativeConfig) -> Result<()> {
+    // Aux tags have to exist before the tag groups referring to them, and tag groups have to exist
+    // before folders and hosts can use them.
+    for aux_tag in config.aux_tags.values() {
+        retry_on_conflict(|| aux_tag.apply_to_site(client))?;
+    }
+    for tag_group in config.tag_groups.values() {
+        retry_on_conflict(|| tag_group.apply_to_site(client))?;
+    }
+
+    let site_tag_groups = client
+        .host_tag_groups()
+        .list_host_tag_groups()?
+        .host_tag_groups
+        .into_iter()
+        .map(|tag_group| {
+            let tag_ids = tag_group
+                .extensions
+                .tags
+                .into_iter()
+                .map(|tag| tag.id)
+                .collect();
+            (tag_group.id, tag_ids)
+        })
+        .collect();
+    let site_aux_tags = client
+        .aux_tags()
+        .list_aux_tags()?
+        .aux_tags
+        .into_iter()
+        .map(|aux_tag| aux_tag.id)
+        .collect();
+    config.verify_tags_against_site(&site_tag_groups, &site_aux_tags)
+}
+
+fn process_groups(client: &checkmk_client::Client, config: &DeclarativeConfig) -> Result<()> {
+    // Groups have to exist before folders, hosts and rules can refer to them.
+    for (group_type, groups) in config.groups() {
+        for group in groups.values() {
+            retry_on_conflict(|| group.apply_to_site(group_type, client))?;
+        }
+    }
+    Ok(())
+}
+
+fn process_time_periods(client: &checkmk_client::Client, config: &DeclarativeConfig) -> Result<()> {
+    for time_period in ordered_time_periods(&config.time_periods) {
+        retry_on_conflict(|| time_period.apply_to_site(&config.time_periods, client))?;
+    }
+    Ok(())
+}
+
+fn process_passwords(
+    client: &checkmk_client::Client,
+    config: &DeclarativeConfig,
+    fingerprint_key: &[u8],
+) -> Result<()> {
+    // Passwords can be owned by contact groups, and have to exist before rules can refer to them.
+    for password in config.passwords.values() {
+        retry_on_conflict(|| password.apply_to_site(fingerprint_key, client))?;
+    }
+    Ok(())
+}
+
+fn process_users(
+    client: &checkmk_client::Client,
+    config: &DeclarativeConfig,
+    own_username: &str,
+    fingerprint_key: &[u8],
+) -> Result<()> {
+    for user in config.users.values() {
+        retry_on_conflict(|| user.apply_to_site(fingerprint_key, client))?;
+    }
+
+    let mut unmanaged_users = config.unmanaged_users.clone();
+    unmanaged_users.push(own_username.to_owned());
+    remove_orphaned_users(
+        &config.users,
+        config.removed_users.unwrap_or_default(),
+        &unmanaged_users,
+        client,
+    )
+}
+
+fn prune_orphaned_groups(
+    client: &checkmk_client::Client,
+    config: &DeclarativeConfig,
+) -> Result<()> {
+    // Groups can only be removed once folders, hosts and rules don't refer to them anymore.
+    if let Some(managed_group_prefix) = &config.managed_group_prefix {
+        for (group_type, groups) in config.groups() {
+            prune_groups(group_type, groups, managed_group_prefix, client)?;
+        }
+    }
+    Ok(())
+}
+
+fn process_folders(
+    client: &checkmk_client::Client,
+    config: &DeclarativeConfig,
+    summary: &mut Summary,
+) -> Result<()> {
+    let root_folder = &config.folders.root_folder;
+    apply_folders(client, root_folder, summary)?;
+    // Cluster hosts can only be created once all their nodes exist, so they are applied after all
+    // other hosts have been.
+    for host in config
+        .folders
+        .iter()
+        .flat_map(|folder| folder.hosts.iter().flatten())
+        .filter(|host| host.is_cluster())
+    {
+        retry_on_conflict(|| host.apply_to_site(client, summary))?;
+    }
+    config.folders.remove_orphaned_host_rules(client)?;
+    Ok(())
+}
+
+fn process_dcd_connections(
+    client: &checkmk_client::Client,
+    config: &DeclarativeConfig,
+) -> Result<()> {
+    // DCD connections create hosts in folders, which therefore have to exist first.
+    for dcd_connection in config.dcd_connections.values() {
+        dcd_connection.apply_to_site(client)?;
+    }
+    Ok(())
+}
+
+fn apply_folders(
+    client: &checkmk_client::Client,
+    folder: &Folder,
+    summary: &mut Summary,
+) -> Result<()> {
+    retry_on_conflict(|| folder.apply_to_site(client))?;
     for folder in folder.folders.values() {
-        apply_folders(client, folder)?;
+        apply_folders(client, folder, summary)?;
     }
     if let Some(hosts) = &folder.hosts {
-        for host in hosts {
-            host.apply_to_site(client)?;
+        for host in hosts.iter().filter(|host| !host.is_cluster()) {
+            retry_on_conflict(|| host.apply_to_site(client, summary))?;
         }
     }
-    if let Some(rulesets) = &folder.rulesets {
-        for ruleset in rulesets.values() {
-            ruleset.apply_to_site(client)?;
-        }
+    for ruleset in folder.effective_rulesets() {
+        ruleset.apply_to_site(client)?;
     }
     Ok(())
 }
@@ -91,9 +359,7 @@ fn apply_pending_changes(client: &checkmk_client::Client) -> Result<()> {
             println!("change_info: {:#?}", c);
             Ok(())
         }
-        Err(error @ checkmk_client::ClientError::HttpRequestError(_)) if error.is_status(422) => {
-            Ok(())
-        }
+        Err(error) if error.is_status(422) => Ok(()),
         Err(e) => Err(e.into()),
     }
 }
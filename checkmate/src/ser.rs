@@ -0,0 +1,67 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{
+    Serialize,
+    Serializer,
+};
+use std::{
+    collections::{
+        BTreeMap,
+        HashMap,
+    },
+    rc::Rc,
+};
+
+pub fn serialize_rc<S, T>(value: &Rc<T>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Serialize,
+{
+    (**value).serialize(serializer)
+}
+
+/// Serialize a map of `Rc`-wrapped values, ordered by key so the output is stable across runs.
+pub fn serialize_map_values_as_rc<S, K, V>(
+    map: &HashMap<K, Rc<V>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    K: Serialize + Ord,
+    V: Serialize,
+{
+    serializer.collect_map(
+        map.iter()
+            .map(|(key, value)| (key, &**value))
+            .collect::<BTreeMap<_, _>>(),
+    )
+}
+
+pub fn serialize_optional_map_values_as_rc<S, K, V>(
+    map: &Option<HashMap<K, Rc<V>>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    K: Serialize + Ord,
+    V: Serialize,
+{
+    match map {
+        Some(map) => serialize_map_values_as_rc(map, serializer),
+        None => serializer.serialize_none(),
+    }
+}
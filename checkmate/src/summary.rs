@@ -0,0 +1,45 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+/// Changes that checkmate deliberately did not apply, which are reported once the configuration has
+/// been applied.
+#[derive(Debug, Default)]
+pub struct Summary {
+    skipped: Vec<String>,
+}
+
+impl Summary {
+    /// Record that a change was skipped, and why.
+    ///
+    /// The same change is only recorded once, even if it was skipped again when the object was
+    /// re-read after a conflicting modification.
+    pub fn skip(&mut self, message: String) {
+        println!("{}", message);
+        if !self.skipped.contains(&message) {
+            self.skipped.push(message);
+        }
+    }
+
+    pub fn print(&self) {
+        if self.skipped.is_empty() {
+            return;
+        }
+        println!("The following changes were skipped:");
+        for message in &self.skipped {
+            println!("  - {}", message);
+        }
+    }
+}
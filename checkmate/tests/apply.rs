@@ -0,0 +1,524 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use checkmk_test_support::FakeCheckmk;
+use serde_json::json;
+use std::{
+    ffi::OsStr,
+    path::{
+        Path,
+        PathBuf,
+    },
+    process::{
+        Command,
+        Output,
+    },
+};
+
+const CONFIG: &str = r#"
+users:
+  jdoe:
+    fullname: Jane Doe
+    email: jane.doe@domain.invalid
+    auth:
+      type: password
+      secret:
+        env: CHECKMATE_TEST_USER_PASSWORD
+
+managed_group_prefix: cm_
+host_groups:
+  cm_linux:
+    alias: Linux servers
+contact_groups:
+  cm_admins:
+    alias: Administrators
+
+passwords:
+  proxmox:
+    title: Proxmox VE
+    owner: cm_admins
+    secret:
+      env: CHECKMATE_TEST_PROXMOX_PASSWORD
+
+time_periods:
+  workhours:
+    alias: Work hours
+    active_time_ranges:
+    - day: all
+      time_ranges:
+      - start: "08:00"
+        end: "18:00"
+    exclude:
+    - holidays
+  holidays:
+    alias: Holidays
+    exceptions:
+    - date: "2026-12-24"
+      time_ranges:
+      - start: "00:00"
+        end: "24:00"
+
+aux_tags:
+  monitored_24x7:
+    title: Monitored around the clock
+    topic: Monitoring
+
+tag_groups:
+  environment:
+    title: Environment
+    tags:
+    - id: prod
+      title: Production
+      aux_tags:
+      - monitored_24x7
+    - id: test
+      title: Test
+
+folders:
+  /:
+    title: Main
+    attributes:
+      labels:
+        checkmate/managed_by: checkmate
+      contactgroups:
+        groups:
+        - cm_admins
+    rulesets:
+      periodic_discovery:
+        rules:
+        - id: autodiscover
+          conditions:
+            host_labels:
+            - key: checkmate/autodiscover
+              operator: is
+              value: 'true'
+          value_raw: "{'check_interval': 30.0}"
+    /Project:
+      title: Project
+      attributes:
+        labels:
+          project: P
+      /Production:
+        title: Project - Production
+        rulesets:
+          host_check_commands:
+            rules:
+            - id: ping
+              properties:
+                description: Check hosts through ping
+              value_raw: "('ping', {})"
+          special_agents:proxmox_ve:
+            rules:
+            - id: proxmox
+              value_raw: "{'username': 'monitoring', 'password': {{password:proxmox}}}"
+        hosts:
+        - host_name: host1.prod.domain.invalid
+          attributes:
+            ipaddress: "127.0.0.1"
+            tag_environment: prod
+            labels:
+              checkmate/autodiscover: 'true'
+          rulesets:
+            host_check_commands:
+              rules:
+              - id: smart
+                value_raw: "'smart'"
+        - host_name: host2.prod.domain.invalid
+          attributes:
+            ipaddress: "127.0.0.2"
+        - host_name: cluster.prod.domain.invalid
+          cluster_nodes:
+          - host1.prod.domain.invalid
+          - host2.prod.domain.invalid
+"#;
+
+/// Write the configuration to a file unique to the test.
+fn write_config(test_name: &str, config: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("{}.yaml", test_name));
+    std::fs::write(&path, config).expect("failed to write configuration");
+    path
+}
+
+/// Run `checkmate apply` against the server, with the additional environment variables.
+fn run_apply(server_url: &str, site: &str, config_file: &Path, envs: &[(&str, &OsStr)]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_checkmate"))
+        .arg("apply")
+        .arg("--server-url")
+        .arg(server_url)
+        .arg("--site")
+        .arg(site)
+        .arg("--secret")
+        .arg("secret")
+        .arg("--config-file")
+        .arg(config_file)
+        .arg("--no-proxy")
+        .arg("--fingerprint-key")
+        .arg("fingerprint-key")
+        .env("CHECKMATE_TEST_USER_PASSWORD", "user-secret-1")
+        .env("CHECKMATE_TEST_PROXMOX_PASSWORD", "proxmox-secret-1")
+        .envs(envs.iter().copied())
+        .output()
+        .expect("failed to run checkmate")
+}
+
+/// Run `checkmate apply` against the fake server, returning its output.
+fn apply(checkmk: &FakeCheckmk, config_file: &Path) -> String {
+    let output = run_apply(&checkmk.server_url(), checkmk.site(), config_file, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    assert!(
+        output.status.success(),
+        "checkmate apply failed:\n{}\n{}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    stdout
+}
+
+#[test]
+fn second_apply_is_a_no_op() {
+    let checkmk = FakeCheckmk::start();
+    let config_file = write_config("second_apply_is_a_no_op", CONFIG);
+
+    apply(&checkmk, &config_file);
+    assert!(!checkmk.take_changes().is_empty());
+    assert_eq!(checkmk.activations(), 1);
+    assert_eq!(
+        checkmk.host("host1.prod.domain.invalid").unwrap()["extensions"]["folder"],
+        "/Project/Production"
+    );
+    assert_eq!(
+        checkmk.host("cluster.prod.domain.invalid").unwrap()["extensions"]["cluster_nodes"],
+        json!(["host1.prod.domain.invalid", "host2.prod.domain.invalid"])
+    );
+    // The rule declared on the host comes before the rule of its folder.
+    let host_check_commands = checkmk.rules("host_check_commands");
+    assert_eq!(host_check_commands.len(), 2);
+    assert_eq!(host_check_commands[0]["extensions"]["value_raw"], "'smart'");
+    assert_eq!(checkmk.rules("periodic_discovery").len(), 1);
+
+    assert!(checkmk.object("user_config", "jdoe").unwrap()["extensions"]
+        ["checkmate_auth_fingerprint"]
+        .is_string());
+    assert_eq!(
+        checkmk.object("contact_group_config", "cm_admins").unwrap()["title"],
+        "Administrators"
+    );
+    assert_eq!(
+        checkmk.rules("special_agents:proxmox_ve")[0]["extensions"]["value_raw"],
+        "{'username': 'monitoring', 'password': ('cmk_postprocessed', 'stored_password', \
+         ('proxmox', ''))}"
+    );
+    assert_eq!(
+        checkmk.object("aux_tag", "monitored_24x7").unwrap()["extensions"]["topic"],
+        "Monitoring"
+    );
+    // Checkmk refers to excluded time periods by their alias.
+    assert_eq!(
+        checkmk.object("time_period", "workhours").unwrap()["extensions"]["exclude"],
+        json!(["Holidays"])
+    );
+
+    apply(&checkmk, &config_file);
+    assert_eq!(checkmk.take_changes(), Vec::<String>::new());
+    assert_eq!(checkmk.activations(), 1);
+}
+
+#[test]
+fn apply_sends_rotated_user_secret() {
+    let checkmk = FakeCheckmk::start();
+    let config_file = write_config("apply_sends_rotated_user_secret", CONFIG);
+
+    apply(&checkmk, &config_file);
+    checkmk.take_changes();
+    let fingerprint = checkmk.object("user_config", "jdoe").unwrap()["extensions"]
+        ["checkmate_auth_fingerprint"]
+        .clone();
+
+    let output = run_apply(
+        &checkmk.server_url(),
+        checkmk.site(),
+        &config_file,
+        &[("CHECKMATE_TEST_USER_PASSWORD", OsStr::new("user-secret-2"))],
+    );
+    assert!(output.status.success());
+    assert_eq!(checkmk.take_changes(), vec!["Modified user jdoe"]);
+    assert_ne!(
+        checkmk.object("user_config", "jdoe").unwrap()["extensions"]["checkmate_auth_fingerprint"],
+        fingerprint
+    );
+}
+
+#[test]
+fn apply_sends_rotated_password() {
+    let checkmk = FakeCheckmk::start();
+    let config_file = write_config("apply_sends_rotated_password", CONFIG);
+
+    apply(&checkmk, &config_file);
+    checkmk.take_changes();
+
+    let output = run_apply(
+        &checkmk.server_url(),
+        checkmk.site(),
+        &config_file,
+        &[(
+            "CHECKMATE_TEST_PROXMOX_PASSWORD",
+            OsStr::new("proxmox-secret-2"),
+        )],
+    );
+    assert!(output.status.success());
+    assert_eq!(checkmk.take_changes(), vec!["Modified password proxmox"]);
+}
+
+#[test]
+fn apply_reverts_changes_made_on_the_site() {
+    let checkmk = FakeCheckmk::start();
+    let config_file = write_config("apply_reverts_changes_made_on_the_site", CONFIG);
+
+    apply(&checkmk, &config_file);
+    let declared_attributes =
+        checkmk.host("host2.prod.domain.invalid").unwrap()["extensions"]["attributes"].clone();
+    checkmk.set_host_attributes(
+        "host2.prod.domain.invalid",
+        json!({"ipaddress": "192.0.2.1"}),
+    );
+    checkmk.take_changes();
+
+    apply(&checkmk, &config_file);
+    assert_eq!(
+        checkmk.take_changes(),
+        vec!["Modified attributes of host host2.prod.domain.invalid"]
+    );
+    assert_eq!(
+        checkmk.host("host2.prod.domain.invalid").unwrap()["extensions"]["attributes"],
+        declared_attributes
+    );
+    assert_eq!(checkmk.activations(), 2);
+}
+
+#[test]
+fn apply_removes_rules_no_longer_declared_on_a_host() {
+    let config = CONFIG.replace(
+        "            ipaddress: \"127.0.0.2\"\n",
+        "            ipaddress: \"127.0.0.2\"\n          rulesets:\n            \
+         extra_host_conf:max_check_attempts:\n              rules:\n              - id: retries\n                \
+         value_raw: '3'\n",
+    );
+    let config_file = write_config("apply_removes_rules_no_longer_declared_on_a_host", &config);
+    let checkmk = FakeCheckmk::start();
+
+    apply(&checkmk, &config_file);
+    assert_eq!(checkmk.rules("extra_host_conf:max_check_attempts").len(), 1);
+    checkmk.take_changes();
+
+    // The ruleset is not declared anywhere else anymore.
+    let config_file = write_config(
+        "apply_removes_rules_no_longer_declared_on_a_host_removed",
+        CONFIG,
+    );
+    apply(&checkmk, &config_file);
+    assert_eq!(checkmk.rules("extra_host_conf:max_check_attempts").len(), 0);
+    assert_eq!(
+        checkmk.take_changes(),
+        vec!["Deleted rule in ruleset extra_host_conf:max_check_attempts in folder /Project/Production"]
+    );
+}
+
+#[test]
+fn apply_removes_orphaned_managed_groups() {
+    let config = CONFIG.replace(
+        "contact_groups:\n",
+        "contact_groups:\n  cm_operators:\n    alias: Operators\n",
+    );
+    let config_file = write_config("apply_removes_orphaned_managed_groups", &config);
+    let checkmk = FakeCheckmk::start();
+
+    apply(&checkmk, &config_file);
+    assert!(checkmk
+        .object("contact_group_config", "cm_operators")
+        .is_some());
+    checkmk.take_changes();
+
+    let config_file = write_config("apply_removes_orphaned_managed_groups_removed", CONFIG);
+    apply(&checkmk, &config_file);
+    assert!(checkmk
+        .object("contact_group_config", "cm_operators")
+        .is_none());
+    assert_eq!(
+        checkmk.take_changes(),
+        vec!["Deleted contact group cm_operators"]
+    );
+}
+
+#[test]
+fn apply_refuses_to_remove_tags_used_on_the_site() {
+    let checkmk = FakeCheckmk::start();
+    let config_file = write_config("apply_refuses_to_remove_tags_used_on_the_site", CONFIG);
+
+    apply(&checkmk, &config_file);
+    // The host is tagged outside of the configuration, like a user editing it in the UI would.
+    checkmk.set_host_attributes(
+        "host2.prod.domain.invalid",
+        json!({"ipaddress": "127.0.0.2", "tag_environment": "test"}),
+    );
+    checkmk.take_changes();
+
+    let changed_config_file = write_config(
+        "apply_refuses_to_remove_tags_used_on_the_site_changed",
+        &CONFIG.replace("    - id: test\n      title: Test\n", ""),
+    );
+    let output = run_apply(
+        &checkmk.server_url(),
+        checkmk.site(),
+        &changed_config_file,
+        &[],
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains(
+        "Tags test of tag group environment are removed, but still used by hosts, folders or \
+         rules on the site"
+    ));
+    assert_eq!(checkmk.take_changes(), Vec::<String>::new());
+}
+
+#[test]
+fn apply_retries_update_after_conflicting_modification() {
+    let checkmk = FakeCheckmk::start();
+    let config_file = write_config(
+        "apply_retries_update_after_conflicting_modification",
+        CONFIG,
+    );
+
+    apply(&checkmk, &config_file);
+    checkmk.set_host_attributes(
+        "host1.prod.domain.invalid",
+        json!({"ipaddress": "192.0.2.1"}),
+    );
+    checkmk.conflict_on_next_update();
+
+    let output = apply(&checkmk, &config_file);
+    assert_eq!(
+        output
+            .matches("host1.prod.domain.invalid: updating existing host")
+            .count(),
+        2
+    );
+    assert_eq!(
+        checkmk.host("host1.prod.domain.invalid").unwrap()["extensions"]["attributes"]["ipaddress"],
+        "127.0.0.1"
+    );
+}
+
+#[test]
+fn recorded_apply_can_be_replayed_without_server() {
+    let config = CONFIG.replace(
+        "            ipaddress: \"127.0.0.2\"\n",
+        "            ipaddress: \"127.0.0.2\"\n            management_protocol: ipmi\n            \
+         management_ipmi_credentials:\n              username: ADMIN\n              password: \
+         ipmi-secret-4711\n",
+    );
+    let config_file = write_config("recorded_apply_can_be_replayed_without_server", &config);
+    let cassette = PathBuf::from(env!("CARGO_TARGET_TMPDIR"))
+        .join("recorded_apply_can_be_replayed_without_server.json");
+
+    let checkmk = FakeCheckmk::start();
+    let (server_url, site) = (checkmk.server_url(), checkmk.site().to_owned());
+    let output = run_apply(
+        &server_url,
+        &site,
+        &config_file,
+        &[("CHECKMK_CLIENT_RECORD", cassette.as_os_str())],
+    );
+    assert!(output.status.success());
+    assert!(
+        checkmk.host("host2.prod.domain.invalid").unwrap()["extensions"]["attributes"]
+            ["management_ipmi_credentials"]
+            .is_object()
+    );
+    drop(checkmk);
+
+    let recorded = std::fs::read_to_string(&cassette).expect("cassette was written");
+    assert!(recorded.contains("host2.prod.domain.invalid"));
+    assert!(!recorded.contains("ipmi-secret-4711"));
+    assert!(!recorded.contains("proxmox-secret-1"));
+
+    let output = run_apply(
+        &server_url,
+        &site,
+        &config_file,
+        &[("CHECKMK_CLIENT_REPLAY", cassette.as_os_str())],
+    );
+    assert!(
+        output.status.success(),
+        "replaying failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let changed_config_file = write_config(
+        "recorded_apply_can_be_replayed_without_server_changed",
+        &config.replace("127.0.0.2", "127.0.0.3"),
+    );
+    let output = run_apply(
+        &server_url,
+        &site,
+        &changed_config_file,
+        &[("CHECKMK_CLIENT_REPLAY", cassette.as_os_str())],
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Request does not match the cassette"));
+}
+
+#[test]
+fn recording_redacts_explicit_passwords_of_rules() {
+    let config = CONFIG.replace(
+        "    rulesets:\n      periodic_discovery:\n",
+        "    rulesets:\n      special_agents:vsphere:\n        rules:\n        - id: vcenter\n          \
+         value_raw: \"{'user': 'monitoring', 'secret': ('cmk_postprocessed', \
+         'explicit_password', ('uuid-vcenter', 'rule-secret-0815'))}\"\n      \
+         special_agents:ipmi_sensors:\n        rules:\n        - id: ipmi\n          value_raw: \
+         \"('freeipmi', {'username': 'ADMIN', 'password': ('password', 'rule-secret-4712')})\"\n      \
+         periodic_discovery:\n",
+    );
+    let config_file = write_config("recording_redacts_explicit_passwords_of_rules", &config);
+    let cassette = PathBuf::from(env!("CARGO_TARGET_TMPDIR"))
+        .join("recording_redacts_explicit_passwords_of_rules.json");
+
+    let checkmk = FakeCheckmk::start();
+    for _ in 0..2 {
+        let output = run_apply(
+            &checkmk.server_url(),
+            checkmk.site(),
+            &config_file,
+            &[("CHECKMK_CLIENT_RECORD", cassette.as_os_str())],
+        );
+        assert!(
+            output.status.success(),
+            "recording failed:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let recorded = std::fs::read_to_string(&cassette).expect("cassette was written");
+        assert!(recorded.contains("uuid-vcenter"));
+        assert!(!recorded.contains("rule-secret-0815"));
+        assert!(!recorded.contains("rule-secret-4712"));
+        checkmk.take_changes();
+    }
+    // The rules were read back unredacted while recording, so the second apply changed nothing.
+    assert_eq!(checkmk.activations(), 1);
+    assert_eq!(
+        checkmk.rules("special_agents:vsphere")[0]["extensions"]["value_raw"],
+        "{'user': 'monitoring', 'secret': ('cmk_postprocessed', 'explicit_password', \
+         ('uuid-vcenter', 'rule-secret-0815'))}"
+    );
+}
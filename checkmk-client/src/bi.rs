@@ -0,0 +1,315 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{
+    Client,
+    Result,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ShowBiPackResponse {
+    pub id: String,
+    pub extensions: BiPack,
+    pub members: BiPackMembers,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ListBiPacksResponse {
+    #[serde(rename = "value")]
+    pub packs: Vec<BiLink>,
+}
+
+/// The rules and aggregations that are part of a BI pack.
+#[derive(Clone, Debug, Deserialize)]
+pub struct BiPackMembers {
+    pub rules: BiPackMemberLinks,
+    pub aggregations: BiPackMemberLinks,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct BiPackMemberLinks {
+    #[serde(rename = "value")]
+    pub links: Vec<BiLink>,
+}
+
+/// A link to a BI object, whose title is the ID of the object.
+#[derive(Clone, Debug, Deserialize)]
+pub struct BiLink {
+    pub title: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BiPack {
+    pub title: String,
+    #[serde(default)]
+    pub contact_groups: Vec<String>,
+    #[serde(default)]
+    pub public: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BiRule {
+    pub pack_id: String,
+    pub id: String,
+    #[serde(flatten)]
+    pub config: BiRuleConfig,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BiRuleConfig {
+    pub properties: BiRuleProperties,
+    /// The child nodes of the rule, which are aggregated into the state of the rule.
+    pub nodes: Vec<BiNode>,
+    #[serde(default)]
+    pub params: BiParams,
+    #[serde(default)]
+    pub aggregation_function: BiAggregationFunction,
+    #[serde(default)]
+    pub computation_options: BiRuleComputationOptions,
+    #[serde(default = "default_node_visualization")]
+    pub node_visualization: Value,
+}
+
+fn default_node_visualization() -> Value {
+    serde_json::json!({"type": "none", "style_config": {}})
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BiRuleProperties {
+    pub title: String,
+    #[serde(default)]
+    pub comment: String,
+    #[serde(default)]
+    pub docu_url: String,
+    #[serde(default)]
+    pub icon: String,
+    #[serde(default)]
+    pub state_messages: BTreeMap<String, String>,
+}
+
+/// The names of the arguments a rule expects, which can be used as `$NAME$` within the rule.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BiParams {
+    #[serde(default)]
+    pub arguments: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BiAggregationFunction {
+    Worst {
+        count: u32,
+        restrict_state: u8,
+    },
+    Best {
+        count: u32,
+        restrict_state: u8,
+    },
+    CountOk {
+        levels_ok: Value,
+        levels_warn: Value,
+    },
+}
+
+impl Default for BiAggregationFunction {
+    fn default() -> Self {
+        Self::Worst {
+            count: 1,
+            restrict_state: 2,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BiRuleComputationOptions {
+    #[serde(default)]
+    pub disabled: bool,
+}
+
+/// A node of a BI rule or aggregation, which searches for hosts or services and creates a child
+/// node for every match.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BiNode {
+    #[serde(default)]
+    pub search: BiSearch,
+    pub action: BiAction,
+}
+
+/// How a node finds the hosts or services it creates child nodes for. The parameters of host and
+/// service searches are passed to Checkmk as-is.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BiSearch {
+    #[default]
+    Empty,
+    HostSearch {
+        #[serde(flatten)]
+        parameters: BTreeMap<String, Value>,
+    },
+    ServiceSearch {
+        #[serde(flatten)]
+        parameters: BTreeMap<String, Value>,
+    },
+    FixedArguments {
+        arguments: Vec<Value>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BiAction {
+    CallARule {
+        rule_id: String,
+        #[serde(default)]
+        params: BiParams,
+    },
+    StateOfHost {
+        host_regex: String,
+    },
+    StateOfService {
+        host_regex: String,
+        service_regex: String,
+    },
+    StateOfRemainingServices {
+        host_regex: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BiAggregation {
+    pub pack_id: String,
+    pub id: String,
+    #[serde(flatten)]
+    pub config: BiAggregationConfig,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BiAggregationConfig {
+    #[serde(default)]
+    pub comment: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub customer: Option<String>,
+    #[serde(default)]
+    pub groups: BiAggregationGroups,
+    pub node: BiNode,
+    #[serde(default)]
+    pub computation_options: BiAggregationComputationOptions,
+    #[serde(default = "default_aggregation_visualization")]
+    pub aggregation_visualization: Value,
+}
+
+fn default_aggregation_visualization() -> Value {
+    serde_json::json!({"ignore_rule_styles": false, "layout_id": "builtin_default", "line_style": "round"})
+}
+
+/// The groups an aggregation is shown in, either by name or as a path of nested groups.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BiAggregationGroups {
+    #[serde(default)]
+    pub names: Vec<String>,
+    #[serde(default)]
+    pub paths: Vec<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BiAggregationComputationOptions {
+    #[serde(default)]
+    pub disabled: bool,
+    #[serde(default)]
+    pub use_hard_states: bool,
+    #[serde(default)]
+    pub escalate_downtimes_as_warn: bool,
+}
+
+pub trait BiApi {
+    fn bi(&self) -> BiClient<'_>;
+}
+
+pub struct BiClient<'a>(&'a Client);
+
+impl BiApi for Client {
+    fn bi(&self) -> BiClient<'_> {
+        BiClient(self)
+    }
+}
+
+impl BiClient<'_> {
+    pub fn create_bi_pack(&self, pack_id: &str, pack: &BiPack) -> Result<ShowBiPackResponse> {
+        self.0.post(format!("/objects/bi_pack/{}", pack_id), pack)
+    }
+
+    pub fn update_bi_pack(&self, pack_id: &str, pack: &BiPack) -> Result<ShowBiPackResponse> {
+        self.0.put(format!("/objects/bi_pack/{}", pack_id), pack)
+    }
+
+    pub fn list_bi_packs(&self) -> Result<ListBiPacksResponse> {
+        self.0.get("/domain-types/bi_pack/collections/all")
+    }
+
+    pub fn show_bi_pack(&self, pack_id: &str) -> Result<ShowBiPackResponse> {
+        self.0.get(format!("/objects/bi_pack/{}", pack_id))
+    }
+
+    pub fn delete_bi_pack(&self, pack_id: &str) -> Result<()> {
+        self.0.delete(format!("/objects/bi_pack/{}", pack_id))
+    }
+
+    pub fn create_bi_rule(&self, rule: &BiRule) -> Result<BiRule> {
+        self.0.post(format!("/objects/bi_rule/{}", rule.id), rule)
+    }
+
+    pub fn update_bi_rule(&self, rule: &BiRule) -> Result<BiRule> {
+        self.0.put(format!("/objects/bi_rule/{}", rule.id), rule)
+    }
+
+    pub fn show_bi_rule(&self, rule_id: &str) -> Result<BiRule> {
+        self.0.get(format!("/objects/bi_rule/{}", rule_id))
+    }
+
+    pub fn delete_bi_rule(&self, rule_id: &str) -> Result<()> {
+        self.0.delete(format!("/objects/bi_rule/{}", rule_id))
+    }
+
+    pub fn create_bi_aggregation(&self, aggregation: &BiAggregation) -> Result<BiAggregation> {
+        self.0.post(
+            format!("/objects/bi_aggregation/{}", aggregation.id),
+            aggregation,
+        )
+    }
+
+    pub fn update_bi_aggregation(&self, aggregation: &BiAggregation) -> Result<BiAggregation> {
+        self.0.put(
+            format!("/objects/bi_aggregation/{}", aggregation.id),
+            aggregation,
+        )
+    }
+
+    pub fn show_bi_aggregation(&self, aggregation_id: &str) -> Result<BiAggregation> {
+        self.0
+            .get(format!("/objects/bi_aggregation/{}", aggregation_id))
+    }
+
+    pub fn delete_bi_aggregation(&self, aggregation_id: &str) -> Result<()> {
+        self.0
+            .delete(format!("/objects/bi_aggregation/{}", aggregation_id))
+    }
+}
@@ -0,0 +1,542 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Recording the exchanges with a checkmk server into cassette files, and replaying them later
+//! without a server.
+//!
+//! While recording, every request and its response are appended to the cassette, with secrets like
+//! passwords and SNMP communities replaced by `<redacted>`, including passwords written explicitly
+//! into the values of rules. The client itself still receives the unredacted responses. While
+//! replaying, every request has to match the next recorded one, otherwise it fails with
+//! [`ClientError::CassetteMismatch`].
+//!
+//! Besides configuring it through [`ClientBuilder::cassette`](crate::ClientBuilder::cassette), a
+//! cassette can be enabled through the environment variables [`RECORD_ENV`] and [`REPLAY_ENV`],
+//! which contain the path of the cassette file.
+
+use crate::{
+    models::REDACTED,
+    ClientError,
+    Next,
+    Result,
+    Transport,
+};
+use reqwest::{
+    blocking::{
+        Request,
+        Response,
+    },
+    header::{
+        self,
+        HeaderMap,
+    },
+    StatusCode,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::Value;
+use std::{
+    collections::{
+        BTreeMap,
+        VecDeque,
+    },
+    fs::File,
+    io::{
+        self,
+        Seek,
+        SeekFrom,
+        Write,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    sync::Mutex,
+};
+
+/// The environment variable containing the path of a cassette to record to.
+pub const RECORD_ENV: &str = "CHECKMK_CLIENT_RECORD";
+/// The environment variable containing the path of a cassette to replay from.
+pub const REPLAY_ENV: &str = "CHECKMK_CLIENT_REPLAY";
+
+/// The response headers that are recorded, since the client relies on them.
+const RECORDED_HEADERS: &[header::HeaderName] =
+    &[header::CONTENT_TYPE, header::ETAG, header::LOCATION];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Send requests to the server, and record them alongside their responses.
+    Record,
+    /// Answer requests with the recorded responses, without contacting the server.
+    Replay,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub if_match: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+}
+
+impl Cassette {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let cassette = std::fs::read(path).map_err(|e| {
+            ClientError::InvalidConfiguration(format!(
+                "failed to read cassette {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        serde_json::from_slice(&cassette).map_err(|e| {
+            ClientError::InvalidConfiguration(format!("invalid cassette {}: {}", path.display(), e))
+        })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let cassette = serde_json::to_string_pretty(self).expect("cassettes can be serialized");
+        std::fs::write(path, cassette + "\n").map_err(|e| {
+            ClientError::Cassette(format!(
+                "failed to write cassette {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+}
+
+impl RecordedRequest {
+    fn from_request(request: &Request) -> Self {
+        Self {
+            method: request.method().to_string(),
+            path: request.url().path().to_owned(),
+            query: request.url().query().map(ToOwned::to_owned),
+            if_match: request
+                .headers()
+                .get(header::IF_MATCH)
+                .and_then(|if_match| if_match.to_str().ok())
+                .map(ToOwned::to_owned),
+            body: request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .filter(|body| !body.is_empty())
+                .map(parse_body),
+        }
+    }
+}
+
+impl RecordedResponse {
+    fn new(status: StatusCode, headers: &HeaderMap, body: &[u8]) -> Self {
+        let headers = RECORDED_HEADERS
+            .iter()
+            .filter_map(|name| {
+                let value = headers.get(name)?.to_str().ok()?;
+                Some((name.as_str().to_owned(), value.to_owned()))
+            })
+            .collect();
+        Self {
+            status: status.as_u16(),
+            headers,
+            body: (!body.is_empty()).then(|| parse_body(body)),
+        }
+    }
+
+    fn to_response(&self) -> Result<Response> {
+        let mut response = http::Response::builder().status(self.status);
+        for (name, value) in &self.headers {
+            response = response.header(name, value);
+        }
+        let body = match &self.body {
+            None => Vec::new(),
+            // Bodies that were not JSON are recorded as-is.
+            Some(Value::String(body)) if !self.is_json() => body.clone().into_bytes(),
+            Some(body) => body.to_string().into_bytes(),
+        };
+        response
+            .body(body)
+            .map(Into::into)
+            .map_err(|e| ClientError::Cassette(format!("invalid recorded response: {}", e)))
+    }
+
+    fn is_json(&self) -> bool {
+        self.headers
+            .get(header::CONTENT_TYPE.as_str())
+            .is_some_and(|content_type| content_type.contains("json"))
+    }
+}
+
+/// Parse the body as JSON with all secrets redacted, or keep it as a string if it is not JSON.
+fn parse_body(body: &[u8]) -> Value {
+    match serde_json::from_slice(body) {
+        Ok(mut body) => {
+            redact_secrets(&mut body);
+            body
+        }
+        Err(_) => Value::String(String::from_utf8_lossy(body).into_owned()),
+    }
+}
+
+fn is_secret(key: &str) -> bool {
+    matches!(key, "password" | "secret" | "community" | "passphrase")
+        || key.ends_with("_password")
+        || key.ends_with("_secret")
+        || key.ends_with("_token")
+}
+
+fn redact_secrets(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                match value {
+                    Value::String(_) if is_secret(key) => {
+                        *value = Value::String(REDACTED.to_owned());
+                    }
+                    Value::String(value_raw) if key == "value_raw" => {
+                        *value_raw = redact_rule_passwords(value_raw);
+                    }
+                    _ => redact_secrets(value),
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
+}
+
+/// A token of the Python literal in the `value_raw` of a rule.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token<'a> {
+    /// A string literal, with the byte range of its content within the `value_raw`.
+    Str(&'a str, usize, usize),
+    Punct(char),
+    Other,
+}
+
+fn tokenize(value_raw: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut chars = value_raw.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '\'' | '"' => {
+                let mut end = value_raw.len();
+                while let Some((index, next)) = chars.next() {
+                    if next == '\\' {
+                        chars.next();
+                    } else if next == c {
+                        end = index;
+                        break;
+                    }
+                }
+                let content = start + c.len_utf8();
+                tokens.push(Token::Str(&value_raw[content..end], content, end));
+            }
+            '(' | ')' | ',' | ':' => tokens.push(Token::Punct(c)),
+            c if c.is_whitespace() => {}
+            _ => tokens.push(Token::Other),
+        }
+    }
+    tokens
+}
+
+/// Redact the passwords written explicitly into the `value_raw` of a rule, which are either
+/// `('password', '<password>')`, `('cmk_postprocessed', 'explicit_password', ('<id>',
+/// '<password>'))`, or strings following secret-like keys of dicts, like `{'password':
+/// '<password>'}`.
+fn redact_rule_passwords(value_raw: &str) -> String {
+    use Token::{
+        Punct,
+        Str,
+    };
+
+    let tokens = tokenize(value_raw);
+    let mut passwords = Vec::new();
+    for index in 0..tokens.len() {
+        match tokens[index..] {
+            [Str("password", ..), Punct(','), Str(_, start, end), ..]
+                if index > 0 && tokens[index - 1] == Punct('(') =>
+            {
+                passwords.push((start, end));
+            }
+            [Str(key, ..), Punct(':'), Str(_, start, end), ..] if is_secret(key) => {
+                passwords.push((start, end));
+            }
+            [Str("explicit_password", ..), Punct(','), Punct('('), ..] => {
+                // The password follows the ID of the explicit password.
+                if let Some([Str(..), Punct(','), Str(_, start, end)]) =
+                    tokens.get(index + 3..index + 6)
+                {
+                    passwords.push((*start, *end));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut redacted = value_raw.to_owned();
+    passwords.sort_unstable();
+    for (start, end) in passwords.into_iter().rev() {
+        redacted.replace_range(start..end, REDACTED);
+    }
+    redacted
+}
+
+/// The end of a cassette file, after the last interaction.
+const CASSETTE_END: &str = "\n  ]\n}\n";
+
+/// Middleware recording every request and its response, appending each interaction to the
+/// cassette file as soon as it happened.
+pub(crate) struct Recorder {
+    path: PathBuf,
+    /// The cassette file, once the first interaction was recorded.
+    file: Mutex<Option<File>>,
+}
+
+impl Recorder {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            file: Mutex::new(None),
+        }
+    }
+
+    fn record(&self, interaction: &Interaction) -> Result<()> {
+        let mut file = self.file.lock().expect("cassette lock is poisoned");
+        let result = match &mut *file {
+            Some(file) => append_interaction(file, interaction, false),
+            None => File::create(&self.path)
+                .and_then(|created| append_interaction(file.insert(created), interaction, true)),
+        };
+        result.map_err(|e| {
+            ClientError::Cassette(format!(
+                "failed to write cassette {}: {}",
+                self.path.display(),
+                e
+            ))
+        })
+    }
+}
+
+/// Append the interaction to the cassette file, which is a valid cassette afterwards, formatted
+/// like [`Cassette::save`] does.
+///
+/// Instead of writing the whole cassette again, only the end of the file is replaced.
+fn append_interaction(file: &mut File, interaction: &Interaction, first: bool) -> io::Result<()> {
+    let mut recorded = if first {
+        "{\n  \"interactions\": [\n".to_owned()
+    } else {
+        file.seek(SeekFrom::End(-(CASSETTE_END.len() as i64)))?;
+        ",\n".to_owned()
+    };
+    let interaction =
+        serde_json::to_string_pretty(interaction).expect("interactions can be serialized");
+    // JSON strings never contain line breaks, so every line can be indented.
+    let lines = interaction
+        .lines()
+        .map(|line| format!("    {}", line))
+        .collect::<Vec<_>>();
+    recorded.push_str(&lines.join("\n"));
+    recorded.push_str(CASSETTE_END);
+    file.write_all(recorded.as_bytes())
+}
+
+impl crate::Middleware for Recorder {
+    fn handle(&self, request: Request, next: Next<'_>) -> Result<Response> {
+        let recorded_request = RecordedRequest::from_request(&request);
+        let response = next.run(request)?;
+        let (status, version, headers) = (
+            response.status(),
+            response.version(),
+            response.headers().clone(),
+        );
+        let body = response.bytes()?;
+        let recorded_response = RecordedResponse::new(status, &headers, &body);
+
+        // Only the recorded copy has its secrets redacted, the caller gets the original response.
+        let mut response = http::Response::new(body.to_vec());
+        *response.status_mut() = status;
+        *response.version_mut() = version;
+        *response.headers_mut() = headers;
+
+        self.record(&Interaction {
+            request: recorded_request,
+            response: recorded_response,
+        })?;
+        Ok(response.into())
+    }
+}
+
+/// Transport answering requests with the responses recorded in a cassette, in the recorded order.
+pub(crate) struct Replayer {
+    interactions: Mutex<VecDeque<Interaction>>,
+}
+
+impl Replayer {
+    pub(crate) fn new(cassette: Cassette) -> Self {
+        Self {
+            interactions: Mutex::new(cassette.interactions.into()),
+        }
+    }
+}
+
+impl Transport for Replayer {
+    fn send(&self, request: Request) -> Result<Response> {
+        let request = RecordedRequest::from_request(&request);
+        let mut interactions = self.interactions.lock().expect("cassette lock is poisoned");
+        match interactions.front() {
+            Some(interaction) if interaction.request == request => {
+                let interaction = interactions.pop_front().expect("interaction exists");
+                interaction.response.to_response()
+            }
+            Some(interaction) => Err(ClientError::CassetteMismatch(format!(
+                "expected {:?}, got {:?}",
+                interaction.request, request
+            ))),
+            None => Err(ClientError::CassetteMismatch(format!(
+                "no more recorded requests, got {:?}",
+                request
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenizes_python_literals() {
+        use Token::{
+            Other,
+            Punct,
+            Str,
+        };
+
+        assert_eq!(
+            tokenize(r#"{'a': ("b\"c", 1)}"#),
+            [
+                Other,
+                Str("a", 2, 3),
+                Punct(':'),
+                Punct('('),
+                Str(r#"b\"c"#, 8, 12),
+                Punct(','),
+                Other,
+                Punct(')'),
+                Other,
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenizes_unterminated_strings_to_the_end() {
+        assert_eq!(
+            tokenize("('abc"),
+            [Token::Punct('('), Token::Str("abc", 2, 5)]
+        );
+    }
+
+    #[test]
+    fn redacts_passwords_of_rules() {
+        for (value_raw, redacted) in [
+            ("('password', 'secret')", "('password', '<redacted>')"),
+            (
+                "('cmk_postprocessed', 'explicit_password', ('uuid', 'secret'))",
+                "('cmk_postprocessed', 'explicit_password', ('uuid', '<redacted>'))",
+            ),
+            (
+                "{'user': 'monitoring', 'password': 'secret', 'auth_secret': \"secret\"}",
+                "{'user': 'monitoring', 'password': '<redacted>', 'auth_secret': \"<redacted>\"}",
+            ),
+        ] {
+            assert_eq!(redact_rule_passwords(value_raw), redacted);
+        }
+    }
+
+    #[test]
+    fn keeps_everything_but_passwords_of_rules() {
+        for value_raw in [
+            "('cmk_postprocessed', 'stored_password', ('uuid', ''))",
+            "{'user': 'password', 'port': 443}",
+            "['password', 'secret']",
+        ] {
+            assert_eq!(redact_rule_passwords(value_raw), value_raw);
+        }
+    }
+
+    #[test]
+    fn appended_interactions_form_a_cassette() {
+        let interaction = |path: &str| Interaction {
+            request: RecordedRequest {
+                method: "GET".to_owned(),
+                path: path.to_owned(),
+                query: None,
+                if_match: None,
+                body: None,
+            },
+            response: RecordedResponse {
+                status: 200,
+                headers: BTreeMap::new(),
+                body: Some(serde_json::json!({"id": path})),
+            },
+        };
+        let cassette = Cassette {
+            interactions: vec![interaction("/a"), interaction("/b")],
+        };
+        let path = std::env::temp_dir().join(format!(
+            "checkmk-client-cassette-{}.json",
+            std::process::id()
+        ));
+
+        let mut file = File::create(&path).unwrap();
+        append_interaction(&mut file, &cassette.interactions[0], true).unwrap();
+        append_interaction(&mut file, &cassette.interactions[1], false).unwrap();
+        drop(file);
+        let appended = std::fs::read_to_string(&path).unwrap();
+        cassette.save(&path).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(appended, saved);
+    }
+}
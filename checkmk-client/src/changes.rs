@@ -44,13 +44,10 @@ impl ChangesClient<'_> {
 
     pub fn wait_for_activation_completion(&self, id: &str) -> Result<()> {
         self.0
-            .http_client
-            .get(self.0.url_for_endpoint(format!(
+            .execute(self.0.http_client.get(self.0.url_for_endpoint(format!(
                 "/objects/activation_run/{id}/actions/wait-for-completion/invoke"
-            )))
-            .send()
+            ))))
             .map(|_| ())
-            .map_err(Into::into)
     }
 
     pub fn activate_pending_changes(&self, etag: ETag) -> Result<ActivatePendingChangesResponse> {
@@ -83,9 +80,9 @@ pub struct PendingChange {
 
 #[derive(Clone, Debug, Serialize)]
 pub struct ActivatePendingChangesRequest {
-    redirect: bool,
-    sites: Vec<String>,
-    force_foreign_changes: bool,
+    pub(crate) redirect: bool,
+    pub(crate) sites: Vec<String>,
+    pub(crate) force_foreign_changes: bool,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -0,0 +1,138 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{
+    Client,
+    Result,
+};
+use crate::hosts::HostAttributes;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ShowDcdConnectionResponse {
+    pub id: String,
+    pub extensions: DcdConnectionOutputExtensions,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ListDcdConnectionsResponse {
+    #[serde(rename = "value")]
+    pub dcd_connections: Vec<ShowDcdConnectionResponse>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct DcdConnectionOutputExtensions {
+    pub title: String,
+    #[serde(default)]
+    pub comment: String,
+    #[serde(default)]
+    pub documentation_url: String,
+    #[serde(default)]
+    pub disabled: bool,
+    pub site: String,
+    pub connector: DcdConnector,
+}
+
+/// A dynamic configuration connection. Checkmk does not allow updating connections, they have to
+/// be deleted and created again instead.
+#[derive(Clone, Debug, Serialize)]
+pub struct CreateDcdConnectionRequest<'a> {
+    pub dcd_id: &'a str,
+    pub title: &'a str,
+    pub comment: &'a str,
+    pub documentation_url: &'a str,
+    pub disabled: bool,
+    pub site: &'a str,
+    pub connector: &'a DcdConnector,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DcdConnector {
+    pub connector_type: DcdConnectorType,
+    /// How often the connection is executed, in seconds.
+    pub interval: u32,
+    pub creation_rules: Vec<DcdCreationRule>,
+    #[serde(default)]
+    pub discover_on_creation: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub activate_changes_interval: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_deletion_time_after_init: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_cache_age: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validity_period: Option<u32>,
+    /// Regular expressions matching the piggyback source hosts the connection is restricted to.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub restrict_source_hosts: Vec<String>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DcdConnectorType {
+    Piggyback,
+}
+
+/// Which hosts a connection creates, where it creates them, and with which attributes.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DcdCreationRule {
+    pub folder_path: String,
+    #[serde(default)]
+    pub host_attributes: HostAttributes,
+    #[serde(default)]
+    pub delete_hosts: bool,
+    /// Regular expressions matching the names of the hosts to create.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matching_hosts: Vec<String>,
+}
+
+pub trait DcdApi {
+    fn dcd(&self) -> DcdClient<'_>;
+}
+
+pub struct DcdClient<'a>(&'a Client);
+
+impl DcdApi for Client {
+    fn dcd(&self) -> DcdClient<'_> {
+        DcdClient(self)
+    }
+}
+
+impl DcdClient<'_> {
+    pub fn create_dcd_connection(
+        &self,
+        dcd_connection: &CreateDcdConnectionRequest,
+    ) -> Result<ShowDcdConnectionResponse> {
+        self.0
+            .post("/domain-types/dcd/collections/all", dcd_connection)
+    }
+
+    pub fn list_dcd_connections(&self) -> Result<ListDcdConnectionsResponse> {
+        self.0.get("/domain-types/dcd/collections/all")
+    }
+
+    pub fn show_dcd_connection(&self, dcd_id: &str) -> Result<ShowDcdConnectionResponse> {
+        self.0.get(format!("/objects/dcd/{}", dcd_id))
+    }
+
+    pub fn delete_dcd_connection(&self, dcd_id: &str) -> Result<()> {
+        self.0
+            .post_without_response(format!("/objects/dcd/{}/actions/delete/invoke", dcd_id))
+    }
+}
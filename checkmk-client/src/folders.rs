@@ -19,20 +19,24 @@ use super::{
     ETag,
     Result,
 };
-use crate::models::{
-    ContactGroups,
-    TagAddressFamily,
-    TagAgent,
-    TagCriticality,
-    TagNetworking,
-    TagPiggyback,
-    TagSnmpDs,
+use crate::{
+    hosts::SnmpCredentials,
+    models::{
+        ContactGroups,
+        ExtraAttributes,
+        IpmiCredentials,
+        ManagementProtocol,
+        Metadata,
+        NetworkScan,
+        NetworkScanResult,
+        Tags,
+    },
 };
 use serde::{
     Deserialize,
     Serialize,
 };
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct ShowFolderResponse {
@@ -79,19 +83,35 @@ pub struct FolderAttributes {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parents: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub labels: Option<HashMap<String, String>>,
+    pub labels: Option<BTreeMap<String, String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub tag_criticality: Option<TagCriticality>,
+    pub network_scan: Option<NetworkScan>,
+    /// Read-only, never sent to Checkmk.
+    #[serde(skip_serializing)]
+    pub network_scan_result: Option<NetworkScanResult>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub tag_networking: Option<TagNetworking>,
+    pub management_protocol: Option<ManagementProtocol>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub tag_agent: Option<TagAgent>,
+    pub management_snmp_community: Option<SnmpCredentials>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub tag_piggyback: Option<TagPiggyback>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub tag_snmp_ds: Option<TagSnmpDs>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub tag_address_family: Option<TagAddressFamily>,
+    pub management_ipmi_credentials: Option<IpmiCredentials>,
+    /// Read-only, never sent to Checkmk.
+    #[serde(rename = "meta_data", skip_serializing)]
+    pub metadata: Option<Metadata>,
+    #[serde(flatten, skip_serializing_if = "Tags::is_empty")]
+    pub tags: Tags,
+    #[serde(flatten, skip_serializing_if = "ExtraAttributes::is_empty")]
+    pub extra_attributes: ExtraAttributes,
+}
+
+impl FolderAttributes {
+    /// Returns the attributes without those that are reported by Checkmk but can't be set, so
+    /// they can be compared to the attributes as declared.
+    pub fn without_read_only(mut self) -> Self {
+        self.network_scan_result = None;
+        self.metadata = None;
+        self
+    }
 }
 
 pub trait FoldersApi {
@@ -0,0 +1,118 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{
+    Client,
+    ETag,
+    Result,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+/// The kinds of groups Checkmk knows, which all share the same API.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum GroupType {
+    HostGroup,
+    ServiceGroup,
+    ContactGroup,
+}
+
+impl GroupType {
+    fn domain_type(self) -> &'static str {
+        match self {
+            Self::HostGroup => "host_group_config",
+            Self::ServiceGroup => "service_group_config",
+            Self::ContactGroup => "contact_group_config",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ShowGroupResponse {
+    pub id: String,
+    #[serde(rename = "title")]
+    pub alias: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ListGroupsResponse {
+    #[serde(rename = "value")]
+    pub groups: Vec<ShowGroupResponse>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CreateGroupRequest<'a> {
+    pub name: &'a str,
+    pub alias: &'a str,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UpdateGroupRequest<'a> {
+    pub alias: &'a str,
+}
+
+pub trait GroupsApi {
+    fn groups(&self, group_type: GroupType) -> GroupsClient<'_>;
+}
+
+pub struct GroupsClient<'a>(&'a Client, GroupType);
+
+impl GroupsApi for Client {
+    fn groups(&self, group_type: GroupType) -> GroupsClient<'_> {
+        GroupsClient(self, group_type)
+    }
+}
+
+impl GroupsClient<'_> {
+    pub fn create_group(&self, group: &CreateGroupRequest) -> Result<(ShowGroupResponse, ETag)> {
+        self.0.post_with_etag(
+            format!("/domain-types/{}/collections/all", self.1.domain_type()),
+            group,
+        )
+    }
+
+    pub fn update_group(
+        &self,
+        name: &str,
+        etag: ETag,
+        group: &UpdateGroupRequest,
+    ) -> Result<(ShowGroupResponse, ETag)> {
+        self.0.put_if_match_with_etag(
+            format!("/objects/{}/{}", self.1.domain_type(), name),
+            etag,
+            group,
+        )
+    }
+
+    pub fn list_groups(&self) -> Result<ListGroupsResponse> {
+        self.0.get(format!(
+            "/domain-types/{}/collections/all",
+            self.1.domain_type()
+        ))
+    }
+
+    pub fn show_group(&self, name: &str) -> Result<(ShowGroupResponse, ETag)> {
+        self.0
+            .get_with_etag(format!("/objects/{}/{}", self.1.domain_type(), name))
+    }
+
+    pub fn delete_group(&self, name: &str) -> Result<()> {
+        self.0
+            .delete(format!("/objects/{}/{}", self.1.domain_type(), name))
+    }
+}
@@ -0,0 +1,223 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{
+    Client,
+    ETag,
+    Result,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ShowHostTagGroupResponse {
+    pub id: String,
+    pub title: String,
+    pub extensions: HostTagGroupOutputExtensions,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ListHostTagGroupsResponse {
+    #[serde(rename = "value")]
+    pub host_tag_groups: Vec<ShowHostTagGroupResponse>,
+}
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct HostTagGroupOutputExtensions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub help: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<HostTag>,
+}
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct HostTag {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub aux_tags: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CreateHostTagGroupRequest<'a> {
+    pub ident: &'a str,
+    pub title: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic: &'a Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub help: &'a Option<String>,
+    pub tags: Vec<HostTagRequest<'a>>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UpdateHostTagGroupRequest<'a> {
+    pub title: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic: &'a Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub help: &'a Option<String>,
+    pub tags: Vec<HostTagRequest<'a>>,
+    /// Whether Checkmk should remove tags that are no longer part of the group from hosts, folders
+    /// and rules that still use them. If `false`, Checkmk refuses to remove tags that are in use.
+    pub repair: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct HostTagRequest<'a> {
+    pub ident: &'a str,
+    pub title: &'a str,
+    pub aux_tags: &'a [String],
+}
+
+impl<'a> From<&'a HostTag> for HostTagRequest<'a> {
+    fn from(tag: &'a HostTag) -> Self {
+        Self {
+            ident: &tag.id,
+            title: &tag.title,
+            aux_tags: &tag.aux_tags,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ShowAuxTagResponse {
+    pub id: String,
+    pub title: String,
+    pub extensions: AuxTagOutputExtensions,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ListAuxTagsResponse {
+    #[serde(rename = "value")]
+    pub aux_tags: Vec<ShowAuxTagResponse>,
+}
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct AuxTagOutputExtensions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub help: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CreateAuxTagRequest<'a> {
+    pub aux_tag_id: &'a str,
+    pub title: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic: &'a Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub help: &'a Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UpdateAuxTagRequest<'a> {
+    pub title: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic: &'a Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub help: &'a Option<String>,
+}
+
+pub trait HostTagGroupsApi {
+    fn host_tag_groups(&self) -> HostTagGroupsClient<'_>;
+}
+
+pub struct HostTagGroupsClient<'a>(&'a Client);
+
+impl HostTagGroupsApi for Client {
+    fn host_tag_groups(&self) -> HostTagGroupsClient<'_> {
+        HostTagGroupsClient(self)
+    }
+}
+
+impl HostTagGroupsClient<'_> {
+    pub fn create_host_tag_group(
+        &self,
+        host_tag_group: &CreateHostTagGroupRequest,
+    ) -> Result<(ShowHostTagGroupResponse, ETag)> {
+        self.0.post_with_etag(
+            "/domain-types/host_tag_group/collections/all",
+            host_tag_group,
+        )
+    }
+
+    pub fn update_host_tag_group(
+        &self,
+        id: &str,
+        etag: ETag,
+        host_tag_group: &UpdateHostTagGroupRequest,
+    ) -> Result<(ShowHostTagGroupResponse, ETag)> {
+        self.0.put_if_match_with_etag(
+            format!("/objects/host_tag_group/{}", id),
+            etag,
+            host_tag_group,
+        )
+    }
+
+    pub fn list_host_tag_groups(&self) -> Result<ListHostTagGroupsResponse> {
+        self.0.get("/domain-types/host_tag_group/collections/all")
+    }
+
+    pub fn show_host_tag_group(&self, id: &str) -> Result<(ShowHostTagGroupResponse, ETag)> {
+        self.0
+            .get_with_etag(format!("/objects/host_tag_group/{}", id))
+    }
+}
+
+pub trait AuxTagsApi {
+    fn aux_tags(&self) -> AuxTagsClient<'_>;
+}
+
+pub struct AuxTagsClient<'a>(&'a Client);
+
+impl AuxTagsApi for Client {
+    fn aux_tags(&self) -> AuxTagsClient<'_> {
+        AuxTagsClient(self)
+    }
+}
+
+impl AuxTagsClient<'_> {
+    pub fn create_aux_tag(
+        &self,
+        aux_tag: &CreateAuxTagRequest,
+    ) -> Result<(ShowAuxTagResponse, ETag)> {
+        self.0
+            .post_with_etag("/domain-types/aux_tag/collections/all", aux_tag)
+    }
+
+    pub fn update_aux_tag(
+        &self,
+        id: &str,
+        etag: ETag,
+        aux_tag: &UpdateAuxTagRequest,
+    ) -> Result<(ShowAuxTagResponse, ETag)> {
+        self.0
+            .put_if_match_with_etag(format!("/objects/aux_tag/{}", id), etag, aux_tag)
+    }
+
+    pub fn list_aux_tags(&self) -> Result<ListAuxTagsResponse> {
+        self.0.get("/domain-types/aux_tag/collections/all")
+    }
+
+    pub fn show_aux_tag(&self, id: &str) -> Result<(ShowAuxTagResponse, ETag)> {
+        self.0.get_with_etag(format!("/objects/aux_tag/{}", id))
+    }
+}
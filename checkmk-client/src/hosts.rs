@@ -21,18 +21,21 @@ use super::{
 };
 use crate::models::{
     ContactGroups,
-    TagAddressFamily,
-    TagAgent,
-    TagCriticality,
-    TagNetworking,
-    TagPiggyback,
-    TagSnmpDs,
+    ExtraAttributes,
+    IpmiCredentials,
+    LockedBy,
+    ManagementProtocol,
+    Metadata,
+    NetworkScan,
+    NetworkScanResult,
+    Secret,
+    Tags,
 };
 use serde::{
     Deserialize,
     Serialize,
 };
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct ShowHostResponse {
@@ -50,6 +53,26 @@ pub struct CreateHostRequest<'a> {
     pub attributes: &'a Option<HostAttributes>,
 }
 
+#[derive(Clone, Debug, Serialize)]
+pub struct CreateClusterHostRequest<'a> {
+    pub folder: String,
+    pub host_name: &'a str,
+    pub nodes: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attributes: &'a Option<HostAttributes>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UpdateNodesRequest<'a> {
+    pub nodes: &'a [String],
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct UpdateNodesResponse {
+    #[serde(rename = "value")]
+    pub nodes: Vec<String>,
+}
+
 #[derive(Clone, Debug, Serialize)]
 pub struct UpdateHostRequest<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -95,48 +118,135 @@ pub struct HostAttributes {
     )]
     pub additional_ipv6_addresses: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub snmp_community: Option<SnmpCommunity>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub labels: Option<HashMap<String, String>>,
-    // #[serde(skip_serializing_if = "Option::is_none")]
-    // pub network_scan: Option<NetworkScan>,
-    // #[serde(skip_serializing_if = "Option::is_none")]
-    // pub network_scan_result: Option<NetworkScanResult>,
-    // #[serde(skip_serializing_if = "Option::is_none")]
-    // pub management_protocol: Option<ManagementProtocol>,
-    // #[serde(skip_serializing_if = "Option::is_none")]
-    // pub management_address: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub management_snmp_community: Option<SnmpCommunity>,
-    // #[serde(skip_serializing_if = "Option::is_none")]
-    // pub management_ipmi_credentials: Option<IpmiCredentials>,
-    // #[serde(rename = "meta_data", skip_serializing_if = "Option::is_none")]
-    // pub metadata: Option<Metadata>,
-    // #[serde(skip_serializing_if = "Option::is_none")]
-    // pub locked_by: Option<LockedBy>,
+    pub snmp_community: Option<SnmpCredentials>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub locked_attributes: Option<Vec<String>>,
+    pub labels: Option<BTreeMap<String, String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub tag_criticality: Option<TagCriticality>,
+    pub network_scan: Option<NetworkScan>,
+    /// Read-only, never sent to Checkmk.
+    #[serde(skip_serializing)]
+    pub network_scan_result: Option<NetworkScanResult>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub tag_networking: Option<TagNetworking>,
+    pub management_protocol: Option<ManagementProtocol>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub tag_agent: Option<TagAgent>,
+    pub management_address: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub tag_piggyback: Option<TagPiggyback>,
+    pub management_snmp_community: Option<SnmpCredentials>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub tag_snmp_ds: Option<TagSnmpDs>,
+    pub management_ipmi_credentials: Option<IpmiCredentials>,
+    /// Read-only, never sent to Checkmk.
+    #[serde(rename = "meta_data", skip_serializing)]
+    pub metadata: Option<Metadata>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub tag_address_family: Option<TagAddressFamily>,
+    pub locked_by: Option<LockedBy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locked_attributes: Option<Vec<String>>,
+    #[serde(flatten, skip_serializing_if = "Tags::is_empty")]
+    pub tags: Tags,
+    #[serde(flatten, skip_serializing_if = "ExtraAttributes::is_empty")]
+    pub extra_attributes: ExtraAttributes,
+}
+
+impl HostAttributes {
+    /// Returns the attributes without those that are reported by Checkmk but can't be set, so
+    /// they can be compared to the attributes as declared.
+    pub fn without_read_only(mut self) -> Self {
+        self.network_scan_result = None;
+        self.metadata = None;
+        self
+    }
+}
+
+/// The credentials used to query a host or its management board via SNMP.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "SnmpCredentialsRepr")]
+#[serde(tag = "type")]
+pub enum SnmpCredentials {
+    #[serde(rename = "v1_v2_community")]
+    V1V2Community { community: Secret },
+    #[serde(rename = "noAuthNoPriv")]
+    V3NoAuthNoPrivacy { security_name: String },
+    #[serde(rename = "authNoPriv")]
+    V3AuthNoPrivacy {
+        auth_protocol: SnmpAuthProtocol,
+        security_name: String,
+        auth_password: Secret,
+    },
+    #[serde(rename = "authPriv")]
+    V3AuthPrivacy {
+        auth_protocol: SnmpAuthProtocol,
+        security_name: String,
+        auth_password: Secret,
+        privacy_protocol: SnmpPrivacyProtocol,
+        privacy_password: Secret,
+    },
+}
+
+/// Accepts SNMP credentials without a `type`, which are SNMPv1/v2 communities, as well as the
+/// type names used by earlier versions of checkmk-client.
+#[derive(Deserialize)]
+struct SnmpCredentialsRepr {
+    #[serde(default)]
+    r#type: Option<String>,
+    community: Option<Secret>,
+    security_name: Option<String>,
+    auth_protocol: Option<SnmpAuthProtocol>,
+    auth_password: Option<Secret>,
+    privacy_protocol: Option<SnmpPrivacyProtocol>,
+    privacy_password: Option<Secret>,
+}
+
+impl TryFrom<SnmpCredentialsRepr> for SnmpCredentials {
+    type Error = String;
+
+    fn try_from(repr: SnmpCredentialsRepr) -> std::result::Result<Self, Self::Error> {
+        fn required<T>(
+            value: Option<T>,
+            field: &str,
+            r#type: &str,
+        ) -> std::result::Result<T, String> {
+            value.ok_or_else(|| {
+                format!("missing field `{field}` for SNMP credentials of type `{type}`")
+            })
+        }
+
+        let r#type = repr.r#type.as_deref().unwrap_or("v1_v2_community");
+        match r#type {
+            "v1_v2_community" => Ok(Self::V1V2Community {
+                community: required(repr.community, "community", r#type)?,
+            }),
+            "noAuthNoPriv" | "v3_no_auth_no_privacy" => Ok(Self::V3NoAuthNoPrivacy {
+                security_name: required(repr.security_name, "security_name", r#type)?,
+            }),
+            "authNoPriv" | "v3_auth_no_privacy" => Ok(Self::V3AuthNoPrivacy {
+                auth_protocol: required(repr.auth_protocol, "auth_protocol", r#type)?,
+                security_name: required(repr.security_name, "security_name", r#type)?,
+                auth_password: required(repr.auth_password, "auth_password", r#type)?,
+            }),
+            "authPriv" | "v3_auth_privacy" => Ok(Self::V3AuthPrivacy {
+                auth_protocol: required(repr.auth_protocol, "auth_protocol", r#type)?,
+                security_name: required(repr.security_name, "security_name", r#type)?,
+                auth_password: required(repr.auth_password, "auth_password", r#type)?,
+                privacy_protocol: required(repr.privacy_protocol, "privacy_protocol", r#type)?,
+                privacy_password: required(repr.privacy_password, "privacy_password", r#type)?,
+            }),
+            unknown => Err(format!("unknown type of SNMP credentials `{unknown}`")),
+        }
+    }
 }
 
+/// SNMP credentials as modelled by earlier versions of checkmk-client, which only supported
+/// SNMPv1/v2 communities.
+#[deprecated(note = "use `SnmpCredentials`, which also supports SNMPv3 credentials")]
 #[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
 pub struct SnmpCommunity {
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[allow(deprecated)]
     pub r#type: Option<SnmpCommunityType>,
     pub community: String,
 }
 
+#[deprecated(note = "use the variants of `SnmpCredentials` instead")]
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 pub enum SnmpCommunityType {
     #[serde(rename = "v1_v2_community")]
@@ -149,6 +259,58 @@ pub enum SnmpCommunityType {
     V3NoAuthNoPrivacy,
 }
 
+/// Converts a community into SNMPv1/v2 credentials. SNMPv3 types are rejected, since the
+/// community alone lacks the security name and passwords they require.
+#[allow(deprecated)]
+impl TryFrom<SnmpCommunity> for SnmpCredentials {
+    type Error = String;
+
+    fn try_from(community: SnmpCommunity) -> std::result::Result<Self, Self::Error> {
+        match community.r#type {
+            None | Some(SnmpCommunityType::V1V2Community) => Ok(Self::V1V2Community {
+                community: Secret::new(community.community),
+            }),
+            Some(r#type) => Err(format!(
+                "SNMP community of type {type:?} can't be converted into SNMP credentials"
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub enum SnmpAuthProtocol {
+    #[serde(rename = "MD5-96")]
+    Md5_96,
+    #[serde(rename = "SHA-1-96")]
+    Sha1_96,
+    #[serde(rename = "SHA-2-224")]
+    Sha2_224,
+    #[serde(rename = "SHA-2-256")]
+    Sha2_256,
+    #[serde(rename = "SHA-2-384")]
+    Sha2_384,
+    #[serde(rename = "SHA-2-512")]
+    Sha2_512,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub enum SnmpPrivacyProtocol {
+    #[serde(rename = "CBC-DES")]
+    CbcDes,
+    #[serde(rename = "AES-128")]
+    Aes128,
+    #[serde(rename = "3DES-EDE")]
+    TripleDesEde,
+    #[serde(rename = "AES-192")]
+    Aes192,
+    #[serde(rename = "AES-256")]
+    Aes256,
+    #[serde(rename = "AES-192-Blumenthal")]
+    Aes192Blumenthal,
+    #[serde(rename = "AES-256-Blumenthal")]
+    Aes256Blumenthal,
+}
+
 pub trait HostsApi {
     fn hosts(&self) -> HostsClient<'_>;
 }
@@ -167,6 +329,27 @@ impl HostsClient<'_> {
             .post_with_etag("/domain-types/host_config/collections/all", host)
     }
 
+    pub fn create_cluster_host(
+        &self,
+        host: &CreateClusterHostRequest,
+    ) -> Result<(ShowHostResponse, ETag)> {
+        self.0
+            .post_with_etag("/domain-types/host_config/collections/clusters", host)
+    }
+
+    pub fn update_nodes(
+        &self,
+        host_name: &str,
+        etag: ETag,
+        nodes: &[String],
+    ) -> Result<UpdateNodesResponse> {
+        self.0.put_if_match(
+            format!("/objects/host_config/{}/properties/nodes", host_name),
+            etag,
+            &UpdateNodesRequest { nodes },
+        )
+    }
+
     pub fn update_host(
         &self,
         host_name: &str,
@@ -195,3 +378,109 @@ impl HostsClient<'_> {
             .get_with_etag(format!("/objects/host_config/{}", host_name))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn extra_attributes_round_trip() {
+        let attributes = json!({
+            "alias": "Host",
+            "tag_location": "berlin",
+            "inventory_failed": true,
+            "snmp_timing": {"timeout": 2.5, "retries": 3},
+        });
+
+        let parsed: HostAttributes = serde_json::from_value(attributes.clone()).unwrap();
+        assert_eq!(parsed.alias.as_deref(), Some("Host"));
+        assert_eq!(parsed.tags, Tags::from_iter([("location", "berlin")]));
+        assert_eq!(
+            parsed.extra_attributes,
+            ExtraAttributes::from_iter([
+                ("inventory_failed", json!(true)),
+                ("snmp_timing", json!({"timeout": 2.5, "retries": 3})),
+            ])
+        );
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), attributes);
+    }
+
+    #[test]
+    fn snmp_credentials_default_to_communities() {
+        let credentials: SnmpCredentials =
+            serde_json::from_value(json!({"community": "public"})).unwrap();
+
+        assert_eq!(
+            credentials,
+            SnmpCredentials::V1V2Community {
+                community: Secret::new("public"),
+            }
+        );
+    }
+
+    #[test]
+    fn snmp_credentials_accept_legacy_type_names() {
+        let credentials: SnmpCredentials = serde_json::from_value(json!({
+            "type": "v3_auth_no_privacy",
+            "auth_protocol": "SHA-2-256",
+            "security_name": "monitoring",
+            "auth_password": "hunter2",
+        }))
+        .unwrap();
+
+        assert_eq!(
+            credentials,
+            SnmpCredentials::V3AuthNoPrivacy {
+                auth_protocol: SnmpAuthProtocol::Sha2_256,
+                security_name: "monitoring".to_owned(),
+                auth_password: Secret::new("hunter2"),
+            }
+        );
+    }
+
+    #[test]
+    fn snmp_credentials_require_the_fields_of_their_type() {
+        let error = serde_json::from_value::<SnmpCredentials>(json!({
+            "type": "authPriv",
+            "auth_protocol": "SHA-2-256",
+            "security_name": "monitoring",
+            "auth_password": "hunter2",
+        }))
+        .unwrap_err();
+
+        assert!(error
+            .to_string()
+            .contains("missing field `privacy_protocol` for SNMP credentials of type `authPriv`"));
+    }
+
+    #[test]
+    fn snmp_credentials_reject_unknown_types() {
+        let error = serde_json::from_value::<SnmpCredentials>(json!({"type": "v4"})).unwrap_err();
+
+        assert!(error
+            .to_string()
+            .contains("unknown type of SNMP credentials `v4`"));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn snmp_communities_convert_into_credentials() {
+        let community = SnmpCommunity {
+            r#type: None,
+            community: "public".to_owned(),
+        };
+        assert_eq!(
+            SnmpCredentials::try_from(community),
+            Ok(SnmpCredentials::V1V2Community {
+                community: Secret::new("public"),
+            })
+        );
+
+        let community = SnmpCommunity {
+            r#type: Some(SnmpCommunityType::V3AuthPrivacy),
+            community: "public".to_owned(),
+        };
+        assert!(SnmpCredentials::try_from(community).is_err());
+    }
+}
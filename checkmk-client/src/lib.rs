@@ -14,23 +14,76 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod bi;
+pub mod cassette;
 pub mod changes;
+pub mod dcd;
 pub mod folders;
+pub mod groups;
+pub mod host_tags;
 pub mod hosts;
 pub mod models;
+#[cfg(feature = "async")]
+pub mod nonblocking;
+pub mod notification_rules;
+pub mod passwords;
+pub mod redact;
+pub mod retry;
 pub mod rules;
+pub mod site_connections;
+pub mod time_periods;
+pub mod transport;
+pub mod users;
+
+pub use cassette::{
+    Cassette,
+    CassetteMode,
+};
+pub use retry::RetryPolicy;
+pub use transport::{
+    Middleware,
+    Next,
+    Transport,
+};
 
 use reqwest::{
-    blocking::RequestBuilder,
+    blocking::{
+        Request,
+        RequestBuilder,
+        Response,
+    },
     header,
+    StatusCode,
 };
 use serde::{
     de::DeserializeOwned,
+    Deserialize,
     Serialize,
 };
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
 use thiserror::Error;
 
-type ETag = String;
+/// The entity tag of an object, which has to be passed along when modifying the object to ensure
+/// it was not changed in the meantime.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ETag(String);
+
+impl ETag {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for ETag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
 
 #[derive(Debug, Error)]
 pub enum ClientError {
@@ -40,6 +93,22 @@ pub enum ClientError {
     MissingOrInvalidETagError,
     #[error("HTTP request could not be enriched by header: {0}")]
     HttpRequestInvalidHeaderValue(String),
+    #[error("Client could not be configured: {0}")]
+    InvalidConfiguration(String),
+    #[error("The object was modified in the meantime, its ETag does not match anymore")]
+    PreconditionFailed,
+    #[error("Cassette could not be used: {0}")]
+    Cassette(String),
+    #[error("Request does not match the cassette: {0}")]
+    CassetteMismatch(String),
+    #[error("{title} (HTTP {status}){}", format_problem_details(detail, fields))]
+    Api {
+        status: u16,
+        title: String,
+        detail: Option<String>,
+        /// The errors for individual fields of the request, keyed by the dotted path of the field.
+        fields: BTreeMap<String, Vec<String>>,
+    },
 }
 
 impl ClientError {
@@ -48,11 +117,132 @@ impl ClientError {
             Self::HttpRequestError(error) => {
                 error.is_status() && error.status().unwrap() == status_code
             }
+            Self::Api { status, .. } => *status == status_code,
+            Self::PreconditionFailed => status_code == 412,
             _ => false,
         }
     }
 }
 
+impl AsRef<dyn std::error::Error + Send + Sync + 'static> for ClientError {
+    fn as_ref(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+        self
+    }
+}
+
+/// Run an operation that reads an object and modifies it using its ETag, running it again if the
+/// object was modified in the meantime.
+///
+/// The operation has to read the object again on every run and compute the modification based on
+/// it. It is run at most `max_attempts` times, after which the last [`ClientError::PreconditionFailed`]
+/// is returned.
+///
+/// Any error type can be used that gives access to the underlying [`ClientError`], such as
+/// `ClientError` itself or `eyre::Report`.
+pub fn retry_on_precondition_failed<T, E, F>(
+    max_attempts: u32,
+    mut operation: F,
+) -> std::result::Result<T, E>
+where
+    F: FnMut() -> std::result::Result<T, E>,
+    E: AsRef<dyn std::error::Error + Send + Sync + 'static>,
+{
+    let mut attempt = 1;
+    loop {
+        match operation() {
+            Err(error)
+                if attempt < max_attempts
+                    && matches!(
+                        error.as_ref().downcast_ref(),
+                        Some(ClientError::PreconditionFailed)
+                    ) =>
+            {
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn format_problem_details(
+    detail: &Option<String>,
+    fields: &BTreeMap<String, Vec<String>>,
+) -> String {
+    let detail = detail
+        .as_ref()
+        .map(|detail| format!(": {}", detail))
+        .unwrap_or_default();
+    let fields: String = fields
+        .iter()
+        .flat_map(|(field, messages)| {
+            messages
+                .iter()
+                .map(move |message| format!("\n  {}: {}", field, message))
+        })
+        .collect();
+    detail + &fields
+}
+
+/// The problem details checkmk responds with if a request failed.
+#[derive(Debug, Deserialize)]
+struct Problem {
+    title: String,
+    detail: Option<String>,
+    #[serde(default)]
+    fields: serde_json::Value,
+}
+
+impl Problem {
+    fn into_error(self, status: u16) -> ClientError {
+        let mut fields = BTreeMap::new();
+        collect_problem_fields(&mut fields, None, self.fields);
+        ClientError::Api {
+            status,
+            title: self.title,
+            detail: self.detail,
+            fields,
+        }
+    }
+}
+
+/// Flatten the field errors, which checkmk nests for nested fields such as host attributes.
+fn collect_problem_fields(
+    fields: &mut BTreeMap<String, Vec<String>>,
+    path: Option<String>,
+    value: serde_json::Value,
+) {
+    let path_or_root = || path.clone().unwrap_or_else(|| "_schema".to_owned());
+    match value {
+        serde_json::Value::Null => {}
+        serde_json::Value::Object(object) => {
+            for (key, value) in object {
+                let key = match &path {
+                    Some(path) => format!("{}.{}", path, key),
+                    None => key,
+                };
+                collect_problem_fields(fields, Some(key), value);
+            }
+        }
+        serde_json::Value::Array(values) => {
+            for value in values {
+                match value {
+                    serde_json::Value::String(message) => {
+                        fields.entry(path_or_root()).or_default().push(message)
+                    }
+                    value => collect_problem_fields(fields, path.clone(), value),
+                }
+            }
+        }
+        serde_json::Value::String(message) => {
+            fields.entry(path_or_root()).or_default().push(message)
+        }
+        value => fields
+            .entry(path_or_root())
+            .or_default()
+            .push(value.to_string()),
+    }
+}
+
 impl From<header::InvalidHeaderValue> for ClientError {
     fn from(value: header::InvalidHeaderValue) -> Self {
         Self::HttpRequestInvalidHeaderValue(format!("{}", value))
@@ -65,20 +255,234 @@ pub struct Client {
     pub http_client: reqwest::blocking::Client,
     pub server_url: String,
     pub site: String,
+    /// The path of the REST API, relative to the server URL.
+    pub api_base_path: String,
+    /// How requests that failed transiently are retried.
+    pub retry_policy: RetryPolicy,
+    transport: Arc<dyn Transport>,
+    middlewares: Vec<Arc<dyn Middleware>>,
 }
 
-impl Client {
-    pub fn new<S: AsRef<str>>(server_url: S, site: S, username: S, secret: S) -> Result<Self> {
+/// Configures how a [`Client`] connects to the checkmk server.
+pub struct ClientBuilder {
+    server_url: String,
+    site: String,
+    username: String,
+    secret: String,
+    connect_timeout: Option<Duration>,
+    request_timeout: Option<Duration>,
+    ca_bundles: Vec<Vec<u8>>,
+    accept_invalid_certs: bool,
+    proxy: Option<String>,
+    no_proxy: bool,
+    identity: Option<Vec<u8>>,
+    api_base_path: Option<String>,
+    retry_policy: RetryPolicy,
+    transport: Option<Arc<dyn Transport>>,
+    middlewares: Vec<Arc<dyn Middleware>>,
+    cassette: Option<(PathBuf, CassetteMode)>,
+}
+
+impl ClientBuilder {
+    /// The maximum time to wait for a connection to the server to be established.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// The maximum time a request may take, from connecting until the response was read
+    /// completely.
+    ///
+    /// This includes following redirects, so waiting for an activation of changes to complete has
+    /// to finish within this time as well.
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Trust the PEM-encoded CA certificates in the bundle, in addition to the system roots.
+    pub fn add_ca_bundle<B: Into<Vec<u8>>>(mut self, pem_bundle: B) -> Self {
+        self.ca_bundles.push(pem_bundle.into());
+        self
+    }
+
+    /// Accept any certificate presented by the server, including expired and self-signed ones.
+    ///
+    /// This makes the connection vulnerable to man-in-the-middle attacks, and should only be used
+    /// for testing.
+    pub fn danger_accept_invalid_certs(mut self, accept_invalid_certs: bool) -> Self {
+        self.accept_invalid_certs = accept_invalid_certs;
+        self
+    }
+
+    /// Send all requests through the proxy at the given URL, instead of the proxies configured
+    /// through the environment.
+    pub fn proxy<S: AsRef<str>>(mut self, proxy_url: S) -> Self {
+        self.proxy = Some(proxy_url.as_ref().to_owned());
+        self
+    }
+
+    /// Ignore the proxies configured through the environment.
+    pub fn no_proxy(mut self) -> Self {
+        self.no_proxy = true;
+        self
+    }
+
+    /// Authenticate with the PEM-encoded client certificate and private key, for servers requiring
+    /// mutual TLS.
+    pub fn identity<B: Into<Vec<u8>>>(mut self, pem: B) -> Self {
+        self.identity = Some(pem.into());
+        self
+    }
+
+    /// Override the path of the REST API relative to the server URL, which is
+    /// `/<site>/check_mk/api/1.0` by default.
+    pub fn api_base_path<S: AsRef<str>>(mut self, api_base_path: S) -> Self {
+        self.api_base_path = Some(api_base_path.as_ref().trim_end_matches('/').to_owned());
+        self
+    }
+
+    /// Configure how requests that failed transiently are retried, see [`RetryPolicy`].
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Send requests through the given transport instead of reqwest.
+    ///
+    /// The options configuring the connection, like timeouts and certificates, only apply to the
+    /// default reqwest transport. Transport and middleware are only used by the blocking client.
+    pub fn transport<T: Transport + 'static>(mut self, transport: T) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Add a middleware, which is run after all previously added middleware and before the
+    /// transport.
+    pub fn middleware<M: Middleware + 'static>(mut self, middleware: M) -> Self {
+        self.middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    /// Record the exchanges with the server to the cassette file, or replay them from it, see
+    /// [`cassette`].
+    ///
+    /// If not configured, the cassette is taken from the environment variables
+    /// [`cassette::RECORD_ENV`] or [`cassette::REPLAY_ENV`], if set.
+    pub fn cassette<P: Into<PathBuf>>(mut self, path: P, mode: CassetteMode) -> Self {
+        self.cassette = Some((path.into(), mode));
+        self
+    }
+
+    pub fn build(self) -> Result<Client> {
+        let mut http_client = reqwest::blocking::Client::builder()
+            .default_headers(self.default_headers()?)
+            .redirect(Self::redirect_policy())
+            .danger_accept_invalid_certs(self.accept_invalid_certs);
+        if let Some(connect_timeout) = self.connect_timeout {
+            http_client = http_client.connect_timeout(connect_timeout);
+        }
+        if let Some(request_timeout) = self.request_timeout {
+            http_client = http_client.timeout(request_timeout);
+        }
+        for certificate in self.root_certificates()? {
+            http_client = http_client.add_root_certificate(certificate);
+        }
+        if self.no_proxy {
+            http_client = http_client.no_proxy();
+        }
+        if let Some(proxy) = self.configured_proxy()? {
+            http_client = http_client.proxy(proxy);
+        }
+        if let Some(identity) = self.configured_identity()? {
+            http_client = http_client.identity(identity);
+        }
+
+        let http_client = http_client.build()?;
+        let mut transport = self.transport.clone();
+        let mut middlewares = self.middlewares.clone();
+        match self.configured_cassette()? {
+            Some((_, CassetteMode::Replay)) if transport.is_some() => {
+                return Err(ClientError::InvalidConfiguration(
+                    "a cassette can't be replayed through a custom transport".to_owned(),
+                ));
+            }
+            Some((path, CassetteMode::Replay)) => {
+                transport = Some(Arc::new(cassette::Replayer::new(Cassette::load(path)?)));
+            }
+            // Recording happens closest to the transport, to record what is actually sent.
+            Some((path, CassetteMode::Record)) => {
+                middlewares.push(Arc::new(cassette::Recorder::new(path)));
+            }
+            None => {}
+        }
+
+        Ok(Client {
+            transport: transport.unwrap_or_else(|| Arc::new(http_client.clone())),
+            middlewares,
+            http_client,
+            api_base_path: self.api_base_path_or_default(),
+            server_url: self.server_url.trim_end_matches('/').to_owned(),
+            site: self.site,
+            retry_policy: self.retry_policy,
+        })
+    }
+
+    /// Build an asynchronous client, which has to be used within a tokio runtime.
+    ///
+    /// Fails if a transport, middleware or cassette is configured, since the asynchronous client
+    /// does not support them.
+    #[cfg(feature = "async")]
+    pub fn build_async(self) -> Result<nonblocking::Client> {
+        if self.transport.is_some() || !self.middlewares.is_empty() || self.cassette.is_some() {
+            return Err(ClientError::InvalidConfiguration(
+                "transports, middleware and cassettes are not supported by the asynchronous client"
+                    .to_owned(),
+            ));
+        }
+        let mut http_client = reqwest::Client::builder()
+            .default_headers(self.default_headers()?)
+            .redirect(Self::redirect_policy())
+            .danger_accept_invalid_certs(self.accept_invalid_certs);
+        if let Some(connect_timeout) = self.connect_timeout {
+            http_client = http_client.connect_timeout(connect_timeout);
+        }
+        if let Some(request_timeout) = self.request_timeout {
+            http_client = http_client.timeout(request_timeout);
+        }
+        for certificate in self.root_certificates()? {
+            http_client = http_client.add_root_certificate(certificate);
+        }
+        if self.no_proxy {
+            http_client = http_client.no_proxy();
+        }
+        if let Some(proxy) = self.configured_proxy()? {
+            http_client = http_client.proxy(proxy);
+        }
+        if let Some(identity) = self.configured_identity()? {
+            http_client = http_client.identity(identity);
+        }
+
+        Ok(nonblocking::Client {
+            http_client: http_client.build()?,
+            api_base_path: self.api_base_path_or_default(),
+            server_url: self.server_url.trim_end_matches('/').to_owned(),
+            site: self.site,
+            retry_policy: self.retry_policy,
+        })
+    }
+
+    fn default_headers(&self) -> Result<header::HeaderMap> {
         let mut headers = header::HeaderMap::new();
         headers.insert(
             header::AUTHORIZATION,
-            header::HeaderValue::from_str(&format!(
-                "Bearer {} {}",
-                username.as_ref(),
-                secret.as_ref()
-            ))?,
+            header::HeaderValue::from_str(&format!("Bearer {} {}", self.username, self.secret))?,
         );
-        let redirect_policy_custom = reqwest::redirect::Policy::custom(|attempt| {
+        Ok(headers)
+    }
+
+    fn redirect_policy() -> reqwest::redirect::Policy {
+        reqwest::redirect::Policy::custom(|attempt| {
             let url_path = attempt.url().path();
             if url_path.contains("/objects/activation_run/")
                 && url_path.ends_with("/actions/wait-for-completion/invoke")
@@ -87,48 +491,157 @@ impl Client {
             } else {
                 reqwest::redirect::Policy::default().redirect(attempt)
             }
-        });
+        })
+    }
 
-        let http_client = reqwest::blocking::Client::builder()
-            .default_headers(headers)
-            .redirect(redirect_policy_custom)
-            .build()?;
+    fn root_certificates(&self) -> Result<Vec<reqwest::Certificate>> {
+        let mut certificates = Vec::new();
+        for ca_bundle in &self.ca_bundles {
+            certificates.extend(
+                reqwest::Certificate::from_pem_bundle(ca_bundle).map_err(|e| {
+                    ClientError::InvalidConfiguration(format!("invalid CA bundle: {}", e))
+                })?,
+            );
+        }
+        Ok(certificates)
+    }
 
-        Ok(Self {
-            http_client,
+    fn configured_proxy(&self) -> Result<Option<reqwest::Proxy>> {
+        self.proxy
+            .as_ref()
+            .map(|proxy| {
+                reqwest::Proxy::all(proxy).map_err(|e| {
+                    ClientError::InvalidConfiguration(format!("invalid proxy URL: {}", e))
+                })
+            })
+            .transpose()
+    }
+
+    fn configured_identity(&self) -> Result<Option<reqwest::Identity>> {
+        self.identity
+            .as_ref()
+            .map(|identity| {
+                reqwest::Identity::from_pem(identity).map_err(|e| {
+                    ClientError::InvalidConfiguration(format!("invalid client identity: {}", e))
+                })
+            })
+            .transpose()
+    }
+
+    fn configured_cassette(&self) -> Result<Option<(PathBuf, CassetteMode)>> {
+        if self.cassette.is_some() {
+            return Ok(self.cassette.clone());
+        }
+        match (
+            std::env::var_os(cassette::RECORD_ENV),
+            std::env::var_os(cassette::REPLAY_ENV),
+        ) {
+            (Some(_), Some(_)) => Err(ClientError::InvalidConfiguration(format!(
+                "only one of {} and {} can be set",
+                cassette::RECORD_ENV,
+                cassette::REPLAY_ENV
+            ))),
+            (Some(path), None) => Ok(Some((path.into(), CassetteMode::Record))),
+            (None, Some(path)) => Ok(Some((path.into(), CassetteMode::Replay))),
+            (None, None) => Ok(None),
+        }
+    }
+
+    fn api_base_path_or_default(&self) -> String {
+        self.api_base_path
+            .clone()
+            .unwrap_or_else(|| format!("/{}/check_mk/api/1.0", self.site))
+    }
+}
+
+impl Client {
+    pub fn new<S: AsRef<str>>(server_url: S, site: S, username: S, secret: S) -> Result<Self> {
+        Self::builder(server_url, site, username, secret).build()
+    }
+
+    /// Create a builder for a client, which allows configuring how the client connects to the
+    /// checkmk server.
+    pub fn builder<S: AsRef<str>>(server_url: S, site: S, username: S, secret: S) -> ClientBuilder {
+        ClientBuilder {
             server_url: server_url.as_ref().to_owned(),
             site: site.as_ref().to_owned(),
-        })
+            username: username.as_ref().to_owned(),
+            secret: secret.as_ref().to_owned(),
+            connect_timeout: None,
+            request_timeout: None,
+            ca_bundles: Vec::new(),
+            accept_invalid_certs: false,
+            proxy: None,
+            no_proxy: false,
+            identity: None,
+            api_base_path: None,
+            retry_policy: RetryPolicy::default(),
+            transport: None,
+            middlewares: Vec::new(),
+            cassette: None,
+        }
     }
 
     fn url_for_endpoint<S: AsRef<str>>(&self, endpoint: S) -> String {
-        format!(
-            "{}/{}/check_mk/api/1.0{}{}",
-            self.server_url,
-            self.site,
-            if endpoint.as_ref().starts_with('/') {
-                ""
-            } else {
-                "/"
-            },
-            endpoint.as_ref()
-        )
+        url_for_endpoint(&self.server_url, &self.api_base_path, endpoint.as_ref())
+    }
+
+    /// Send the request, retrying it according to the client's [`RetryPolicy`].
+    ///
+    /// The response is returned regardless of its status code.
+    fn execute(&self, request_builder: RequestBuilder) -> Result<Response> {
+        let request = request_builder.build()?;
+        let mut attempts = retry::Attempts::new(&self.retry_policy, request.method());
+        let result = loop {
+            let result = match request.try_clone() {
+                Some(attempt_request) if attempts.retries_left() => self.dispatch(attempt_request),
+                // The request cannot be cloned (e.g. because of a streaming body) or this is the
+                // final attempt, so it is sent as-is.
+                _ => break self.dispatch(request),
+            };
+            let backoff = match &result {
+                Ok(response) => attempts.retry_after_status(response.status()),
+                Err(ClientError::HttpRequestError(error)) => attempts.retry_after_error(error),
+                Err(_) => None,
+            };
+            match backoff {
+                Some(backoff) => std::thread::sleep(backoff),
+                None => break result,
+            }
+        };
+        match result {
+            Ok(response) if attempts.deleted_by_earlier_attempt(response.status()) => {
+                Ok(retry::deleted_response().into())
+            }
+            result => result,
+        }
+    }
+
+    /// Pass the request through the middleware to the transport.
+    fn dispatch(&self, request: Request) -> Result<Response> {
+        Next::new(&self.middlewares, self.transport.as_ref()).run(request)
+    }
+
+    /// Send the request through [`Client::execute`], turning error status codes into errors.
+    ///
+    /// If checkmk describes the problem in the response, a [`ClientError::Api`] is returned, unless
+    /// the ETag did not match, which results in a [`ClientError::PreconditionFailed`].
+    fn send(&self, request_builder: RequestBuilder) -> Result<Response> {
+        let response = self.execute(request_builder)?;
+        let Err(error) = response.error_for_status_ref() else {
+            return Ok(response);
+        };
+        let status = response.status();
+        Err(error_from_response(error, status, &response.bytes()?))
     }
 
     fn delete<S: AsRef<str>>(&self, endpoint: S) -> Result<()> {
-        self.http_client
-            .delete(self.url_for_endpoint(endpoint))
-            .send()?
-            .error_for_status()
+        self.send(self.http_client.delete(self.url_for_endpoint(endpoint)))
             .map(|_| ())
-            .map_err(Into::into)
     }
 
     fn get<O: DeserializeOwned, S: AsRef<str>>(&self, endpoint: S) -> Result<O> {
-        self.http_client
-            .get(self.url_for_endpoint(endpoint))
-            .send()?
-            .error_for_status()?
+        self.send(self.http_client.get(self.url_for_endpoint(endpoint)))?
             .json()
             .map_err(Into::into)
     }
@@ -144,18 +657,11 @@ impl Client {
     ) -> Result<O> {
         let mut request_builder = self.http_client.get(self.url_for_endpoint(endpoint));
         request_builder = action(request_builder);
-        request_builder
-            .send()?
-            .error_for_status()?
-            .json()
-            .map_err(Into::into)
+        self.send(request_builder)?.json().map_err(Into::into)
     }
 
     fn get_with_etag<O: DeserializeOwned, S: AsRef<str>>(&self, endpoint: S) -> Result<(O, ETag)> {
-        self.http_client
-            .get(self.url_for_endpoint(endpoint))
-            .send()?
-            .error_for_status()?
+        self.send(self.http_client.get(self.url_for_endpoint(endpoint)))?
             .json_with_etag()
     }
 
@@ -164,13 +670,18 @@ impl Client {
         endpoint: S,
         body: &I,
     ) -> Result<O> {
-        self.http_client
-            .post(self.url_for_endpoint(endpoint))
-            .json(body)
-            .send()?
-            .error_for_status()?
-            .json()
-            .map_err(Into::into)
+        self.send(
+            self.http_client
+                .post(self.url_for_endpoint(endpoint))
+                .json(body),
+        )?
+        .json()
+        .map_err(Into::into)
+    }
+
+    fn post_without_response<S: AsRef<str>>(&self, endpoint: S) -> Result<()> {
+        self.send(self.http_client.post(self.url_for_endpoint(endpoint)))
+            .map(|_| ())
     }
 
     fn post_if_match<I: Serialize, O: DeserializeOwned, S: AsRef<str>>(
@@ -179,14 +690,14 @@ impl Client {
         if_match: ETag,
         body: &I,
     ) -> Result<O> {
-        self.http_client
-            .post(self.url_for_endpoint(endpoint))
-            .header(header::IF_MATCH, header::HeaderValue::from_str(&if_match)?)
-            .json(body)
-            .send()?
-            .error_for_status()?
-            .json()
-            .map_err(Into::into)
+        self.send(
+            self.http_client
+                .post(self.url_for_endpoint(endpoint))
+                .if_match(&if_match)?
+                .json(body),
+        )?
+        .json()
+        .map_err(Into::into)
     }
 
     fn post_with_etag<I: Serialize, O: DeserializeOwned, S: AsRef<str>>(
@@ -194,12 +705,12 @@ impl Client {
         endpoint: S,
         body: &I,
     ) -> Result<(O, ETag)> {
-        self.http_client
-            .post(self.url_for_endpoint(endpoint))
-            .json(body)
-            .send()?
-            .error_for_status()?
-            .json_with_etag()
+        self.send(
+            self.http_client
+                .post(self.url_for_endpoint(endpoint))
+                .json(body),
+        )?
+        .json_with_etag()
     }
 
     fn post_if_match_with_etag<I: Serialize, O: DeserializeOwned, S: AsRef<str>>(
@@ -208,13 +719,43 @@ impl Client {
         if_match: ETag,
         body: &I,
     ) -> Result<(O, ETag)> {
-        self.http_client
-            .post(self.url_for_endpoint(endpoint))
-            .header(header::IF_MATCH, header::HeaderValue::from_str(&if_match)?)
-            .json(body)
-            .send()?
-            .error_for_status()?
-            .json_with_etag()
+        self.send(
+            self.http_client
+                .post(self.url_for_endpoint(endpoint))
+                .if_match(&if_match)?
+                .json(body),
+        )?
+        .json_with_etag()
+    }
+
+    fn put<I: Serialize, O: DeserializeOwned, S: AsRef<str>>(
+        &self,
+        endpoint: S,
+        body: &I,
+    ) -> Result<O> {
+        self.send(
+            self.http_client
+                .put(self.url_for_endpoint(endpoint))
+                .json(body),
+        )?
+        .json()
+        .map_err(Into::into)
+    }
+
+    fn put_if_match<I: Serialize, O: DeserializeOwned, S: AsRef<str>>(
+        &self,
+        endpoint: S,
+        if_match: ETag,
+        body: &I,
+    ) -> Result<O> {
+        self.send(
+            self.http_client
+                .put(self.url_for_endpoint(endpoint))
+                .if_match(&if_match)?
+                .json(body),
+        )?
+        .json()
+        .map_err(Into::into)
     }
 
     fn put_if_match_with_etag<I: Serialize, O: DeserializeOwned, S: AsRef<str>>(
@@ -223,16 +764,39 @@ impl Client {
         if_match: ETag,
         body: &I,
     ) -> Result<(O, ETag)> {
-        self.http_client
-            .put(self.url_for_endpoint(endpoint))
-            .header(header::IF_MATCH, header::HeaderValue::from_str(&if_match)?)
-            .json(body)
-            .send()?
-            .error_for_status()?
-            .json_with_etag()
+        self.send(
+            self.http_client
+                .put(self.url_for_endpoint(endpoint))
+                .if_match(&if_match)?
+                .json(body),
+        )?
+        .json_with_etag()
+    }
+}
+
+/// Builds requests the same way for the blocking and the asynchronous client.
+trait RequestBuilderExt: Sized {
+    /// Only let the request modify the object if it still has the given ETag.
+    fn if_match(self, etag: &ETag) -> Result<Self>;
+}
+
+impl RequestBuilderExt for RequestBuilder {
+    fn if_match(self, etag: &ETag) -> Result<Self> {
+        Ok(self.header(header::IF_MATCH, if_match_header_value(etag)?))
     }
 }
 
+#[cfg(feature = "async")]
+impl RequestBuilderExt for reqwest::RequestBuilder {
+    fn if_match(self, etag: &ETag) -> Result<Self> {
+        Ok(self.header(header::IF_MATCH, if_match_header_value(etag)?))
+    }
+}
+
+fn if_match_header_value(etag: &ETag) -> Result<header::HeaderValue> {
+    header::HeaderValue::from_str(etag.as_str()).map_err(Into::into)
+}
+
 trait ResponseExt<T> {
     fn json_with_etag(self) -> Result<(T, ETag)>;
 }
@@ -242,16 +806,127 @@ where
     T: DeserializeOwned,
 {
     fn json_with_etag(self) -> Result<(T, ETag)> {
-        let etag = self
-            .headers()
-            .get(header::ETAG)
-            .ok_or(ClientError::MissingOrInvalidETagError)
-            .and_then(|etag_header_value| {
-                etag_header_value
-                    .to_str()
-                    .map_err(|_| ClientError::MissingOrInvalidETagError)
-            })?
-            .to_owned();
+        let etag = etag_from_headers(self.headers())?;
         Ok((self.json()?, etag))
     }
 }
+
+fn url_for_endpoint(server_url: &str, api_base_path: &str, endpoint: &str) -> String {
+    format!(
+        "{}{}{}{}",
+        server_url,
+        api_base_path,
+        if endpoint.starts_with('/') { "" } else { "/" },
+        endpoint
+    )
+}
+
+fn etag_from_headers(headers: &header::HeaderMap) -> Result<ETag> {
+    headers
+        .get(header::ETAG)
+        .ok_or(ClientError::MissingOrInvalidETagError)
+        .and_then(|etag_header_value| {
+            etag_header_value
+                .to_str()
+                .map_err(|_| ClientError::MissingOrInvalidETagError)
+        })
+        .map(|etag| ETag(etag.to_owned()))
+}
+
+/// Turn an error status into the most specific error, using the problem details in the body if
+/// checkmk provided them.
+fn error_from_response(error: reqwest::Error, status: StatusCode, body: &[u8]) -> ClientError {
+    if status == StatusCode::PRECONDITION_FAILED {
+        return ClientError::PreconditionFailed;
+    }
+    match serde_json::from_slice::<Problem>(body) {
+        Ok(problem) => problem.into_error(status.as_u16()),
+        Err(_) => error.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn problem_error(problem: serde_json::Value) -> ClientError {
+        serde_json::from_value::<Problem>(problem)
+            .unwrap()
+            .into_error(400)
+    }
+
+    #[test]
+    fn problem_fields_are_flattened_to_dotted_paths() {
+        let error = problem_error(json!({
+            "title": "Bad Request",
+            "status": 400,
+            "detail": "These fields have problems: attributes",
+            "fields": {
+                "attributes": {
+                    "tag_location": ["Unknown tag berlin"],
+                    "contactgroups": {"groups": ["Unknown group admins", "Unknown group ops"]},
+                },
+                "folder": "Not a folder",
+                "_schema": [{"host_name": ["Missing data for required field."]}],
+            },
+        }));
+
+        let ClientError::Api { fields, .. } = &error else {
+            panic!("unexpected error {:?}", error);
+        };
+        assert_eq!(
+            fields,
+            &BTreeMap::from([
+                (
+                    "_schema.host_name".to_owned(),
+                    vec!["Missing data for required field.".to_owned()]
+                ),
+                (
+                    "attributes.contactgroups.groups".to_owned(),
+                    vec![
+                        "Unknown group admins".to_owned(),
+                        "Unknown group ops".to_owned()
+                    ]
+                ),
+                (
+                    "attributes.tag_location".to_owned(),
+                    vec!["Unknown tag berlin".to_owned()]
+                ),
+                ("folder".to_owned(), vec!["Not a folder".to_owned()]),
+            ])
+        );
+        assert_eq!(
+            error.to_string(),
+            "Bad Request (HTTP 400): These fields have problems: attributes\n  \
+             _schema.host_name: Missing data for required field.\n  \
+             attributes.contactgroups.groups: Unknown group admins\n  \
+             attributes.contactgroups.groups: Unknown group ops\n  \
+             attributes.tag_location: Unknown tag berlin\n  \
+             folder: Not a folder"
+        );
+    }
+
+    #[test]
+    fn problem_fields_without_a_path_belong_to_the_schema() {
+        let error = problem_error(json!({
+            "title": "Bad Request",
+            "fields": ["Either folder or host_name is required.", 42],
+        }));
+
+        let ClientError::Api { detail, fields, .. } = &error else {
+            panic!("unexpected error {:?}", error);
+        };
+        assert_eq!(detail, &None);
+        assert_eq!(
+            fields,
+            &BTreeMap::from([(
+                "_schema".to_owned(),
+                vec![
+                    "Either folder or host_name is required.".to_owned(),
+                    "42".to_owned()
+                ]
+            )])
+        );
+    }
+}
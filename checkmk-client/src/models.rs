@@ -15,8 +15,19 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use serde::{
+    de::{
+        IgnoredAny,
+        MapAccess,
+        Visitor,
+    },
     Deserialize,
+    Deserializer,
     Serialize,
+    Serializer,
+};
+use std::{
+    collections::BTreeMap,
+    fmt,
 };
 
 #[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
@@ -33,67 +44,538 @@ pub struct ContactGroups {
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
-pub enum TagCriticality {
-    #[serde(rename = "prod")]
-    Prod,
-    #[serde(rename = "critical")]
-    Critical,
-    #[serde(rename = "test")]
-    Test,
-    #[serde(rename = "offline")]
-    Offline,
+pub enum ManagementProtocol {
+    #[serde(rename = "none")]
+    None,
+    #[serde(rename = "snmp")]
+    Snmp,
+    #[serde(rename = "ipmi")]
+    Ipmi,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
-pub enum TagNetworking {
-    #[serde(rename = "lan")]
-    Lan,
-    #[serde(rename = "wan")]
-    Wan,
-    #[serde(rename = "dmz")]
-    Dmz,
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct IpmiCredentials {
+    pub username: String,
+    pub password: Secret,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
-pub enum TagAgent {
-    #[serde(rename = "cmk-agent")]
-    CmkAgent,
-    #[serde(rename = "all-agents")]
-    AllAgents,
-    #[serde(rename = "special-agents")]
-    SpecialAgents,
-    #[serde(rename = "no-agent")]
-    NoAgent,
+/// A secret value, like a password, which is redacted from debug output.
+///
+/// Secrets are serialized as-is, since they have to be sent to Checkmk, unless they are
+/// serialized through [`Redacted`](crate::redact::Redacted).
+#[derive(Clone, Eq, PartialEq, Default, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+pub(crate) const REDACTED: &str = "<redacted>";
+
+impl Secret {
+    pub fn new<S: Into<String>>(secret: S) -> Self {
+        Self(secret.into())
+    }
+
+    /// Returns the actual secret value.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
-pub enum TagPiggyback {
-    #[serde(rename = "auto-piggyback")]
-    AutoPiggyback,
-    #[serde(rename = "piggyback")]
-    Piggyback,
-    #[serde(rename = "no-piggyback")]
-    NoPiggyback,
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret({REDACTED})")
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Serializing as a newtype struct is transparent for JSON and YAML, but lets `Redacted`
+        // recognize secrets.
+        serializer.serialize_newtype_struct(crate::redact::SECRET_NAME, &self.0)
+    }
+}
+
+impl From<String> for Secret {
+    fn from(secret: String) -> Self {
+        Self(secret)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(secret: &str) -> Self {
+        Self(secret.to_owned())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct NetworkScan {
+    pub addresses: Vec<IpAddresses>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_addresses: Vec<IpAddresses>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scan_interval: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub time_allowed: Vec<TimeAllowedRange>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub set_ip_address: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_as: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag_criticality: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_parallel_pings: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub translate_names: Option<TranslateNames>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum IpAddresses {
+    #[serde(rename = "ip_range")]
+    IpRange {
+        from_address: String,
+        to_address: String,
+    },
+    #[serde(rename = "ip_network")]
+    IpNetwork { network: String },
+    #[serde(rename = "ip_list")]
+    IpList { addresses: Vec<String> },
+    #[serde(rename = "ip_regex_list")]
+    IpRegexList { regexes: Vec<String> },
+}
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct TimeAllowedRange {
+    pub start: String,
+    pub end: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct TranslateNames {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub convert_case: Option<ConvertCase>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drop_domain: Option<bool>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub regex: Vec<RegexReplacement>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hostname_replacement: Vec<HostnameReplacement>,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
-pub enum TagSnmpDs {
-    #[serde(rename = "no-snmp")]
-    NoSnmp,
-    #[serde(rename = "snmp-v2")]
-    SnmpV2,
-    #[serde(rename = "snmp-v1")]
-    SnmpV1,
+pub enum ConvertCase {
+    #[serde(rename = "nop")]
+    Nop,
+    #[serde(rename = "lower")]
+    Lower,
+    #[serde(rename = "upper")]
+    Upper,
+}
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct RegexReplacement {
+    pub expression: String,
+    pub replacement: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct HostnameReplacement {
+    pub hostname: String,
+    pub replacement: String,
+}
+
+/// The result of the last network scan of a folder. This is reported by Checkmk and can't be set.
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct NetworkScanResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<NetworkScanState>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
-pub enum TagAddressFamily {
-    #[serde(rename = "ip-v4-only")]
-    IpV4Only,
-    #[serde(rename = "ip-v6-only")]
-    IpV6Only,
-    #[serde(rename = "ip-v4v6")]
-    IpV4v6,
-    #[serde(rename = "no-ip")]
-    NoIp,
+pub enum NetworkScanState {
+    #[serde(rename = "idle")]
+    Idle,
+    #[serde(rename = "running")]
+    Running,
+    #[serde(rename = "succeeded")]
+    Succeeded,
+    #[serde(rename = "failed")]
+    Failed,
+}
+
+/// Bookkeeping of Checkmk about a host or folder. This is reported by Checkmk and can't be set.
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct Metadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+}
+
+/// The program that locked attributes of a host, e.g. the dynamic configuration daemon (DCD).
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct LockedBy {
+    pub site_id: String,
+    pub program_id: String,
+    pub instance_id: String,
+}
+
+/// Host tags, keyed by tag group ID and mapping to the ID of the tag chosen within that group.
+///
+/// Checkmk represents tags as individual `tag_<group>` attributes on hosts and folders. This type
+/// collects all of them when flattened into an attributes struct, which allows custom tag groups to
+/// be used without checkmk-client knowing about them. The built-in tag groups can be accessed in a
+/// typed manner through [`Tags::get`] and [`Tags::set`].
+///
+/// Tag attributes that are `null` are treated as if they were absent, so a tag can't be unset
+/// through them.
+#[derive(Clone, Debug, Eq, PartialEq, Default)]
+pub struct Tags(BTreeMap<String, String>);
+
+const TAG_ATTRIBUTE_PREFIX: &str = "tag_";
+
+impl Tags {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if the attribute name refers to a tag group.
+    pub fn is_tag_attribute(attribute: &str) -> bool {
+        attribute.starts_with(TAG_ATTRIBUTE_PREFIX)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(group, tag)| (group.as_str(), tag.as_str()))
+    }
+
+    pub fn tag(&self, group_id: &str) -> Option<&str> {
+        self.0.get(group_id).map(String::as_str)
+    }
+
+    pub fn set_tag<G: Into<String>, T: Into<String>>(&mut self, group_id: G, tag_id: T) {
+        self.0.insert(group_id.into(), tag_id.into());
+    }
+
+    pub fn remove_tag(&mut self, group_id: &str) -> Option<String> {
+        self.0.remove(group_id)
+    }
+
+    /// Get the tag of a built-in tag group, if it is set and valid.
+    pub fn get<T: BuiltinTagGroup>(&self) -> Option<T> {
+        self.tag(T::GROUP_ID).and_then(T::from_tag_id)
+    }
+
+    /// Set the tag of a built-in tag group.
+    pub fn set<T: BuiltinTagGroup>(&mut self, tag: T) {
+        self.set_tag(T::GROUP_ID, tag.tag_id());
+    }
+}
+
+impl<G: Into<String>, T: Into<String>> FromIterator<(G, T)> for Tags {
+    fn from_iter<I: IntoIterator<Item = (G, T)>>(iter: I) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(group, tag)| (group.into(), tag.into()))
+                .collect(),
+        )
+    }
+}
+
+impl Serialize for Tags {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(
+            self.0
+                .iter()
+                .map(|(group, tag)| (format!("{TAG_ATTRIBUTE_PREFIX}{group}"), tag)),
+        )
+    }
+}
+
+impl<'de> Deserialize<'de> for Tags {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TagsVisitor;
+
+        impl<'de> Visitor<'de> for TagsVisitor {
+            type Value = Tags;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a map of tag attributes")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut tags = BTreeMap::new();
+                while let Some(key) = map.next_key::<String>()? {
+                    // When flattened, we get to see all attributes that have not been consumed by
+                    // other fields, so we have to skip everything that is not a tag.
+                    match key.strip_prefix(TAG_ATTRIBUTE_PREFIX) {
+                        Some(group) => {
+                            if let Some(tag) = map.next_value::<Option<String>>()? {
+                                tags.insert(group.to_owned(), tag);
+                            }
+                        }
+                        None => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+                Ok(Tags(tags))
+            }
+        }
+
+        deserializer.deserialize_map(TagsVisitor)
+    }
+}
+
+/// A tag group that is built into Checkmk, with its possible tags modelled as an enum.
+pub trait BuiltinTagGroup: Sized + Copy {
+    /// The ID of the tag group, without the `tag_` attribute prefix.
+    const GROUP_ID: &'static str;
+    /// The IDs of all tags of this group.
+    const TAG_IDS: &'static [&'static str];
+
+    fn tag_id(self) -> &'static str;
+    fn from_tag_id(tag_id: &str) -> Option<Self>;
+}
+
+/// Returns the valid tag IDs of a built-in tag group, or `None` if the tag group is not built-in.
+pub fn builtin_tag_ids(group_id: &str) -> Option<&'static [&'static str]> {
+    fn ids<T: BuiltinTagGroup>() -> (&'static str, &'static [&'static str]) {
+        (T::GROUP_ID, T::TAG_IDS)
+    }
+
+    [
+        ids::<TagCriticality>(),
+        ids::<TagNetworking>(),
+        ids::<TagAgent>(),
+        ids::<TagPiggyback>(),
+        ids::<TagSnmpDs>(),
+        ids::<TagAddressFamily>(),
+    ]
+    .into_iter()
+    .find(|(id, _)| *id == group_id)
+    .map(|(_, tag_ids)| tag_ids)
+}
+
+/// Host and folder attributes that are not modelled by checkmk-client, like custom host attributes
+/// defined on the site.
+///
+/// When flattened into an attributes struct, this collects all attributes that are neither consumed
+/// by other fields nor tags, so they are passed through to Checkmk unchanged.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct ExtraAttributes(BTreeMap<String, serde_json::Value>);
+
+impl ExtraAttributes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &serde_json::Value)> {
+        self.0.iter().map(|(name, value)| (name.as_str(), value))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&serde_json::Value> {
+        self.0.get(name)
+    }
+
+    pub fn insert<N: Into<String>>(
+        &mut self,
+        name: N,
+        value: serde_json::Value,
+    ) -> Option<serde_json::Value> {
+        self.0.insert(name.into(), value)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<serde_json::Value> {
+        self.0.remove(name)
+    }
+}
+
+impl<N: Into<String>> FromIterator<(N, serde_json::Value)> for ExtraAttributes {
+    fn from_iter<I: IntoIterator<Item = (N, serde_json::Value)>>(iter: I) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(name, value)| (name.into(), value))
+                .collect(),
+        )
+    }
+}
+
+impl Serialize for ExtraAttributes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ExtraAttributes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ExtraAttributesVisitor;
+
+        impl<'de> Visitor<'de> for ExtraAttributesVisitor {
+            type Value = ExtraAttributes;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a map of attributes")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut attributes = BTreeMap::new();
+                while let Some(key) = map.next_key::<String>()? {
+                    // Tags are collected by `Tags`, so we must not collect them twice.
+                    if Tags::is_tag_attribute(&key) {
+                        map.next_value::<IgnoredAny>()?;
+                    } else {
+                        attributes.insert(key, map.next_value()?);
+                    }
+                }
+                Ok(ExtraAttributes(attributes))
+            }
+        }
+
+        deserializer.deserialize_map(ExtraAttributesVisitor)
+    }
+}
+
+/// The auxiliary tags that are built into Checkmk.
+pub const BUILTIN_AUX_TAG_IDS: &[&str] =
+    &["ip-v4", "ip-v6", "snmp", "tcp", "checkmk-agent", "ping"];
+
+macro_rules! builtin_tag_group {
+    ($name:ident, $group_id:literal, { $($variant:ident => $tag_id:literal),+ $(,)? }) => {
+        #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+        pub enum $name {
+            $(
+                #[serde(rename = $tag_id)]
+                $variant,
+            )+
+        }
+
+        impl BuiltinTagGroup for $name {
+            const GROUP_ID: &'static str = $group_id;
+            const TAG_IDS: &'static [&'static str] = &[$($tag_id),+];
+
+            fn tag_id(self) -> &'static str {
+                match self {
+                    $(Self::$variant => $tag_id,)+
+                }
+            }
+
+            fn from_tag_id(tag_id: &str) -> Option<Self> {
+                match tag_id {
+                    $($tag_id => Some(Self::$variant),)+
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+builtin_tag_group!(TagCriticality, "criticality", {
+    Prod => "prod",
+    Critical => "critical",
+    Test => "test",
+    Offline => "offline",
+});
+
+builtin_tag_group!(TagNetworking, "networking", {
+    Lan => "lan",
+    Wan => "wan",
+    Dmz => "dmz",
+});
+
+builtin_tag_group!(TagAgent, "agent", {
+    CmkAgent => "cmk-agent",
+    AllAgents => "all-agents",
+    SpecialAgents => "special-agents",
+    NoAgent => "no-agent",
+});
+
+builtin_tag_group!(TagPiggyback, "piggyback", {
+    AutoPiggyback => "auto-piggyback",
+    Piggyback => "piggyback",
+    NoPiggyback => "no-piggyback",
+});
+
+builtin_tag_group!(TagSnmpDs, "snmp_ds", {
+    NoSnmp => "no-snmp",
+    SnmpV2 => "snmp-v2",
+    SnmpV1 => "snmp-v1",
+});
+
+builtin_tag_group!(TagAddressFamily, "address_family", {
+    IpV4Only => "ip-v4-only",
+    IpV6Only => "ip-v6-only",
+    IpV4v6 => "ip-v4v6",
+    NoIp => "no-ip",
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Attributes {
+        alias: Option<String>,
+        #[serde(flatten)]
+        tags: Tags,
+    }
+
+    #[test]
+    fn tags_strip_the_attribute_prefix() {
+        let attributes: Attributes = serde_json::from_value(json!({
+            "alias": "Host",
+            "tag_criticality": "prod",
+            "tag_location": "berlin",
+            "labels": {"os": "linux"},
+        }))
+        .unwrap();
+
+        assert_eq!(attributes.alias.as_deref(), Some("Host"));
+        assert_eq!(
+            attributes.tags,
+            Tags::from_iter([("criticality", "prod"), ("location", "berlin")])
+        );
+        assert_eq!(attributes.tags.get(), Some(TagCriticality::Prod));
+    }
+
+    #[test]
+    fn tags_serialize_with_the_attribute_prefix() {
+        let attributes = Attributes {
+            alias: None,
+            tags: Tags::from_iter([("location", "berlin")]),
+        };
+
+        assert_eq!(
+            serde_json::to_value(&attributes).unwrap(),
+            json!({"alias": null, "tag_location": "berlin"})
+        );
+    }
+
+    #[test]
+    fn tags_drop_null_values() {
+        let attributes: Attributes = serde_json::from_value(json!({
+            "tag_criticality": null,
+            "tag_location": "berlin",
+        }))
+        .unwrap();
+
+        assert_eq!(attributes.tags, Tags::from_iter([("location", "berlin")]));
+    }
 }
@@ -0,0 +1,264 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! An asynchronous client for the checkmk REST API, which has to be used within a tokio runtime.
+//!
+//! The requests and responses are shared with the blocking client, only the clients for the
+//! individual APIs differ by being `async`. Requests are retried according to the same
+//! [`RetryPolicy`] as the ones of the blocking client.
+//!
+//! Only the APIs for folders, hosts, rules and changes are available. Requests are sent directly
+//! through reqwest, so [`Transport`](crate::Transport)s, [`Middleware`](crate::Middleware) and
+//! [`Cassette`](crate::Cassette)s don't apply to them: asynchronous requests can neither be
+//! intercepted nor recorded or replayed, and the cassette environment variables are ignored.
+
+pub mod changes;
+pub mod folders;
+pub mod hosts;
+pub mod rules;
+
+use crate::{
+    error_from_response,
+    etag_from_headers,
+    retry,
+    url_for_endpoint,
+    ETag,
+    RequestBuilderExt,
+    Result,
+    RetryPolicy,
+};
+use reqwest::{
+    RequestBuilder,
+    Response,
+};
+use serde::{
+    de::DeserializeOwned,
+    Serialize,
+};
+
+pub struct Client {
+    pub http_client: reqwest::Client,
+    pub server_url: String,
+    pub site: String,
+    /// The path of the REST API, relative to the server URL.
+    pub api_base_path: String,
+    /// How requests that failed transiently are retried.
+    pub retry_policy: RetryPolicy,
+}
+
+impl Client {
+    pub fn new<S: AsRef<str>>(server_url: S, site: S, username: S, secret: S) -> Result<Self> {
+        crate::Client::builder(server_url, site, username, secret).build_async()
+    }
+
+    fn url_for_endpoint<S: AsRef<str>>(&self, endpoint: S) -> String {
+        url_for_endpoint(&self.server_url, &self.api_base_path, endpoint.as_ref())
+    }
+
+    /// Send the request, retrying it according to the client's [`RetryPolicy`].
+    ///
+    /// The response is returned regardless of its status code.
+    async fn execute(&self, request_builder: RequestBuilder) -> Result<Response> {
+        let request = request_builder.build()?;
+        let mut attempts = retry::Attempts::new(&self.retry_policy, request.method());
+        let result = loop {
+            let result = match request.try_clone() {
+                Some(attempt_request) if attempts.retries_left() => {
+                    self.http_client.execute(attempt_request).await
+                }
+                // The request cannot be cloned (e.g. because of a streaming body) or this is the
+                // final attempt, so it is sent as-is.
+                _ => break self.http_client.execute(request).await,
+            };
+            let backoff = match &result {
+                Ok(response) => attempts.retry_after_status(response.status()),
+                Err(error) => attempts.retry_after_error(error),
+            };
+            match backoff {
+                Some(backoff) => tokio::time::sleep(backoff).await,
+                None => break result,
+            }
+        };
+        match result {
+            Ok(response) if attempts.deleted_by_earlier_attempt(response.status()) => {
+                Ok(retry::deleted_response().into())
+            }
+            result => result.map_err(Into::into),
+        }
+    }
+
+    /// Send the request through [`Client::execute`], turning error status codes into errors.
+    async fn send(&self, request_builder: RequestBuilder) -> Result<Response> {
+        let response = self.execute(request_builder).await?;
+        let Err(error) = response.error_for_status_ref() else {
+            return Ok(response);
+        };
+        let status = response.status();
+        Err(error_from_response(error, status, &response.bytes().await?))
+    }
+
+    async fn delete<S: AsRef<str>>(&self, endpoint: S) -> Result<()> {
+        self.send(self.http_client.delete(self.url_for_endpoint(endpoint)))
+            .await
+            .map(|_| ())
+    }
+
+    async fn get<O: DeserializeOwned, S: AsRef<str>>(&self, endpoint: S) -> Result<O> {
+        self.send(self.http_client.get(self.url_for_endpoint(endpoint)))
+            .await?
+            .json()
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn get_with_action<
+        O: DeserializeOwned,
+        S: AsRef<str>,
+        A: FnOnce(RequestBuilder) -> RequestBuilder,
+    >(
+        &self,
+        endpoint: S,
+        action: A,
+    ) -> Result<O> {
+        let mut request_builder = self.http_client.get(self.url_for_endpoint(endpoint));
+        request_builder = action(request_builder);
+        self.send(request_builder)
+            .await?
+            .json()
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn get_with_etag<O: DeserializeOwned, S: AsRef<str>>(
+        &self,
+        endpoint: S,
+    ) -> Result<(O, ETag)> {
+        json_with_etag(
+            self.send(self.http_client.get(self.url_for_endpoint(endpoint)))
+                .await?,
+        )
+        .await
+    }
+
+    async fn post<I: Serialize, O: DeserializeOwned, S: AsRef<str>>(
+        &self,
+        endpoint: S,
+        body: &I,
+    ) -> Result<O> {
+        self.send(
+            self.http_client
+                .post(self.url_for_endpoint(endpoint))
+                .json(body),
+        )
+        .await?
+        .json()
+        .await
+        .map_err(Into::into)
+    }
+
+    async fn post_if_match<I: Serialize, O: DeserializeOwned, S: AsRef<str>>(
+        &self,
+        endpoint: S,
+        if_match: ETag,
+        body: &I,
+    ) -> Result<O> {
+        self.send(
+            self.http_client
+                .post(self.url_for_endpoint(endpoint))
+                .if_match(&if_match)?
+                .json(body),
+        )
+        .await?
+        .json()
+        .await
+        .map_err(Into::into)
+    }
+
+    async fn post_with_etag<I: Serialize, O: DeserializeOwned, S: AsRef<str>>(
+        &self,
+        endpoint: S,
+        body: &I,
+    ) -> Result<(O, ETag)> {
+        json_with_etag(
+            self.send(
+                self.http_client
+                    .post(self.url_for_endpoint(endpoint))
+                    .json(body),
+            )
+            .await?,
+        )
+        .await
+    }
+
+    async fn post_if_match_with_etag<I: Serialize, O: DeserializeOwned, S: AsRef<str>>(
+        &self,
+        endpoint: S,
+        if_match: ETag,
+        body: &I,
+    ) -> Result<(O, ETag)> {
+        json_with_etag(
+            self.send(
+                self.http_client
+                    .post(self.url_for_endpoint(endpoint))
+                    .if_match(&if_match)?
+                    .json(body),
+            )
+            .await?,
+        )
+        .await
+    }
+
+    async fn put_if_match<I: Serialize, O: DeserializeOwned, S: AsRef<str>>(
+        &self,
+        endpoint: S,
+        if_match: ETag,
+        body: &I,
+    ) -> Result<O> {
+        self.send(
+            self.http_client
+                .put(self.url_for_endpoint(endpoint))
+                .if_match(&if_match)?
+                .json(body),
+        )
+        .await?
+        .json()
+        .await
+        .map_err(Into::into)
+    }
+
+    async fn put_if_match_with_etag<I: Serialize, O: DeserializeOwned, S: AsRef<str>>(
+        &self,
+        endpoint: S,
+        if_match: ETag,
+        body: &I,
+    ) -> Result<(O, ETag)> {
+        json_with_etag(
+            self.send(
+                self.http_client
+                    .put(self.url_for_endpoint(endpoint))
+                    .if_match(&if_match)?
+                    .json(body),
+            )
+            .await?,
+        )
+        .await
+    }
+}
+
+async fn json_with_etag<T: DeserializeOwned>(response: Response) -> Result<(T, ETag)> {
+    let etag = etag_from_headers(response.headers())?;
+    Ok((response.json().await?, etag))
+}
@@ -0,0 +1,66 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use super::Client;
+use crate::{
+    folders::{
+        CreateFolderRequest,
+        ShowFolderResponse,
+        UpdateFolderRequest,
+    },
+    ETag,
+    Result,
+};
+
+pub trait FoldersApi {
+    fn folders(&self) -> FoldersClient<'_>;
+}
+
+pub struct FoldersClient<'a>(&'a Client);
+
+impl FoldersApi for Client {
+    fn folders(&self) -> FoldersClient<'_> {
+        FoldersClient(self)
+    }
+}
+
+impl FoldersClient<'_> {
+    pub async fn create_folder(
+        &self,
+        folder: &CreateFolderRequest<'_>,
+    ) -> Result<(ShowFolderResponse, ETag)> {
+        self.0
+            .post_with_etag("/domain-types/folder_config/collections/all", folder)
+            .await
+    }
+
+    pub async fn update_folder(
+        &self,
+        id: &str,
+        etag: ETag,
+        folder: &UpdateFolderRequest<'_>,
+    ) -> Result<(ShowFolderResponse, ETag)> {
+        self.0
+            .put_if_match_with_etag(format!("/objects/folder_config/{}", id), etag, folder)
+            .await
+    }
+
+    pub async fn show_folder(&self, folder: &str) -> Result<(ShowFolderResponse, ETag)> {
+        self.0
+            .get_with_etag(format!("/objects/folder_config/{}", folder))
+            .await
+    }
+}
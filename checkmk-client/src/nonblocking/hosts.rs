@@ -0,0 +1,109 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use super::Client;
+use crate::{
+    hosts::{
+        CreateClusterHostRequest,
+        CreateHostRequest,
+        MoveToFolderRequest,
+        ShowHostResponse,
+        UpdateHostRequest,
+        UpdateNodesRequest,
+        UpdateNodesResponse,
+    },
+    ETag,
+    Result,
+};
+
+pub trait HostsApi {
+    fn hosts(&self) -> HostsClient<'_>;
+}
+
+pub struct HostsClient<'a>(&'a Client);
+
+impl HostsApi for Client {
+    fn hosts(&self) -> HostsClient<'_> {
+        HostsClient(self)
+    }
+}
+
+impl HostsClient<'_> {
+    pub async fn create_host(
+        &self,
+        host: &CreateHostRequest<'_>,
+    ) -> Result<(ShowHostResponse, ETag)> {
+        self.0
+            .post_with_etag("/domain-types/host_config/collections/all", host)
+            .await
+    }
+
+    pub async fn create_cluster_host(
+        &self,
+        host: &CreateClusterHostRequest<'_>,
+    ) -> Result<(ShowHostResponse, ETag)> {
+        self.0
+            .post_with_etag("/domain-types/host_config/collections/clusters", host)
+            .await
+    }
+
+    pub async fn update_nodes(
+        &self,
+        host_name: &str,
+        etag: ETag,
+        nodes: &[String],
+    ) -> Result<UpdateNodesResponse> {
+        self.0
+            .put_if_match(
+                format!("/objects/host_config/{}/properties/nodes", host_name),
+                etag,
+                &UpdateNodesRequest { nodes },
+            )
+            .await
+    }
+
+    pub async fn update_host(
+        &self,
+        host_name: &str,
+        etag: ETag,
+        host: &UpdateHostRequest<'_>,
+    ) -> Result<(ShowHostResponse, ETag)> {
+        self.0
+            .put_if_match_with_etag(format!("/objects/host_config/{}", host_name), etag, host)
+            .await
+    }
+
+    pub async fn move_to_folder(
+        &self,
+        host_name: &str,
+        etag: ETag,
+        target_folder: &str,
+    ) -> Result<(ShowHostResponse, ETag)> {
+        self.0
+            .post_if_match_with_etag(
+                format!("/objects/host_config/{}/actions/move/invoke", host_name),
+                etag,
+                &MoveToFolderRequest { target_folder },
+            )
+            .await
+    }
+
+    pub async fn show_host(&self, host_name: &str) -> Result<(ShowHostResponse, ETag)> {
+        self.0
+            .get_with_etag(format!("/objects/host_config/{}", host_name))
+            .await
+    }
+}
@@ -0,0 +1,75 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use super::Client;
+use crate::{
+    rules::{
+        CreateRuleRequest,
+        ListRulesResponse,
+        MoveToPositionRequest,
+        ShowRuleResponse,
+    },
+    Result,
+};
+
+pub trait RulesApi {
+    fn rules(&self) -> RulesClient<'_>;
+}
+
+pub struct RulesClient<'a>(&'a Client);
+
+impl RulesApi for Client {
+    fn rules(&self) -> RulesClient<'_> {
+        RulesClient(self)
+    }
+}
+
+impl RulesClient<'_> {
+    pub async fn create_rule(&self, rule: &CreateRuleRequest<'_>) -> Result<ShowRuleResponse> {
+        self.0
+            .post("/domain-types/rule/collections/all", rule)
+            .await
+    }
+
+    pub async fn list_rules(&self, ruleset_name: &str) -> Result<ListRulesResponse> {
+        self.0
+            .get_with_action("/domain-types/rule/collections/all", |request_builder| {
+                request_builder.query(&[("ruleset_name", ruleset_name)])
+            })
+            .await
+    }
+
+    pub async fn delete_rule(&self, rule_id: &str) -> Result<()> {
+        self.0.delete(format!("/objects/rule/{}", rule_id)).await
+    }
+
+    pub async fn show_rule(&self, rule_id: &str) -> Result<ShowRuleResponse> {
+        self.0.get(format!("/objects/rule/{}", rule_id)).await
+    }
+
+    pub async fn move_rule_to_position(
+        &self,
+        rule_id: &str,
+        move_to_position: &MoveToPositionRequest,
+    ) -> Result<()> {
+        self.0
+            .post(
+                format!("/objects/rule/{}/actions/move/invoke", rule_id),
+                move_to_position,
+            )
+            .await
+    }
+}
@@ -0,0 +1,254 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{
+    Client,
+    ETag,
+    Result,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ShowNotificationRuleResponse {
+    pub id: String,
+    pub extensions: NotificationRuleOutputExtensions,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ListNotificationRulesResponse {
+    #[serde(rename = "value")]
+    pub notification_rules: Vec<ShowNotificationRuleResponse>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct NotificationRuleOutputExtensions {
+    pub rule_config: NotificationRuleConfig,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NotificationRuleRequest<'a> {
+    pub rule_config: &'a NotificationRuleConfig,
+}
+
+/// An option of a notification rule that can be enabled with a value, or disabled.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum Checkbox<T> {
+    Enabled {
+        value: T,
+    },
+    #[default]
+    Disabled,
+}
+
+impl<T> Checkbox<T> {
+    pub fn value(&self) -> Option<&T> {
+        match self {
+            Self::Enabled { value } => Some(value),
+            Self::Disabled => None,
+        }
+    }
+}
+
+impl<T> From<Option<T>> for Checkbox<T> {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => Self::Enabled { value },
+            None => Self::Disabled,
+        }
+    }
+}
+
+/// An option of a notification rule that can only be enabled or disabled.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum Toggle {
+    Enabled,
+    #[default]
+    Disabled,
+}
+
+impl From<bool> for Toggle {
+    fn from(value: bool) -> Self {
+        if value {
+            Self::Enabled
+        } else {
+            Self::Disabled
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NotificationRuleConfig {
+    pub rule_properties: NotificationRuleProperties,
+    pub notification_method: NotificationMethod,
+    pub contact_selection: ContactSelection,
+    pub conditions: NotificationRuleConditions,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct NotificationRuleProperties {
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub comment: String,
+    #[serde(default)]
+    pub documentation_url: String,
+    #[serde(default)]
+    pub do_not_apply_this_rule: Toggle,
+    #[serde(default)]
+    pub allow_users_to_deactivate: Toggle,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NotificationMethod {
+    pub notify_plugin: NotifyPlugin,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "option", rename_all = "snake_case")]
+pub enum NotifyPlugin {
+    CreateNotificationWithTheFollowingParameters { plugin_params: PluginParams },
+    CancelPreviousNotifications { plugin_params: PluginParams },
+}
+
+impl NotifyPlugin {
+    pub fn plugin_params(&self) -> &PluginParams {
+        match self {
+            Self::CreateNotificationWithTheFollowingParameters { plugin_params }
+            | Self::CancelPreviousNotifications { plugin_params } => plugin_params,
+        }
+    }
+}
+
+/// The notification plugin to use, e.g. `mail` or `slack`, and its plugin specific parameters.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PluginParams {
+    pub plugin_name: String,
+    #[serde(flatten)]
+    pub parameters: BTreeMap<String, Value>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContactSelection {
+    #[serde(default)]
+    pub all_contacts_of_the_notified_object: Toggle,
+    #[serde(default)]
+    pub all_users: Toggle,
+    #[serde(default)]
+    pub all_users_with_an_email_address: Toggle,
+    #[serde(default)]
+    pub the_following_users: Checkbox<Vec<String>>,
+    #[serde(default)]
+    pub members_of_contact_groups: Checkbox<Vec<String>>,
+    #[serde(default)]
+    pub explicit_email_addresses: Checkbox<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct NotificationRuleConditions {
+    #[serde(default)]
+    pub match_hosts: Checkbox<Vec<String>>,
+    #[serde(default)]
+    pub exclude_hosts: Checkbox<Vec<String>>,
+    #[serde(default)]
+    pub match_host_labels: Checkbox<Vec<Label>>,
+    #[serde(default)]
+    pub match_host_groups: Checkbox<Vec<String>>,
+    #[serde(default)]
+    pub match_services: Checkbox<Vec<String>>,
+    #[serde(default)]
+    pub exclude_services: Checkbox<Vec<String>>,
+    #[serde(default)]
+    pub match_service_labels: Checkbox<Vec<Label>>,
+    #[serde(default)]
+    pub match_service_groups: Checkbox<Vec<String>>,
+    #[serde(default)]
+    pub match_contact_groups: Checkbox<Vec<String>>,
+    #[serde(default)]
+    pub match_only_during_time_period: Checkbox<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Label {
+    pub key: String,
+    pub value: String,
+}
+
+pub trait NotificationRulesApi {
+    fn notification_rules(&self) -> NotificationRulesClient<'_>;
+}
+
+pub struct NotificationRulesClient<'a>(&'a Client);
+
+impl NotificationRulesApi for Client {
+    fn notification_rules(&self) -> NotificationRulesClient<'_> {
+        NotificationRulesClient(self)
+    }
+}
+
+impl NotificationRulesClient<'_> {
+    /// Create a notification rule. Checkmk always adds new notification rules after all existing
+    /// ones.
+    pub fn create_notification_rule(
+        &self,
+        rule_config: &NotificationRuleConfig,
+    ) -> Result<ShowNotificationRuleResponse> {
+        self.0.post(
+            "/domain-types/notification_rule/collections/all",
+            &NotificationRuleRequest { rule_config },
+        )
+    }
+
+    pub fn update_notification_rule(
+        &self,
+        rule_id: &str,
+        etag: ETag,
+        rule_config: &NotificationRuleConfig,
+    ) -> Result<(ShowNotificationRuleResponse, ETag)> {
+        self.0.put_if_match_with_etag(
+            format!("/objects/notification_rule/{}", rule_id),
+            etag,
+            &NotificationRuleRequest { rule_config },
+        )
+    }
+
+    /// List all notification rules, in the order Checkmk evaluates them.
+    pub fn list_notification_rules(&self) -> Result<ListNotificationRulesResponse> {
+        self.0
+            .get("/domain-types/notification_rule/collections/all")
+    }
+
+    pub fn show_notification_rule(
+        &self,
+        rule_id: &str,
+    ) -> Result<(ShowNotificationRuleResponse, ETag)> {
+        self.0
+            .get_with_etag(format!("/objects/notification_rule/{}", rule_id))
+    }
+
+    pub fn delete_notification_rule(&self, rule_id: &str) -> Result<()> {
+        self.0.post_without_response(format!(
+            "/objects/notification_rule/{}/actions/delete/invoke",
+            rule_id
+        ))
+    }
+}
@@ -0,0 +1,118 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{
+    Client,
+    ETag,
+    Result,
+};
+use crate::models::Secret;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ShowPasswordResponse {
+    pub id: String,
+    pub title: String,
+    pub extensions: PasswordOutputExtensions,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ListPasswordsResponse {
+    #[serde(rename = "value")]
+    pub passwords: Vec<ShowPasswordResponse>,
+}
+
+/// The attributes of a password store entry. Checkmk never reports the password itself.
+#[derive(Clone, Debug, PartialEq, Default, Deserialize)]
+pub struct PasswordOutputExtensions {
+    pub comment: Option<String>,
+    pub documentation_url: Option<String>,
+    pub owned_by: Option<String>,
+    #[serde(default)]
+    pub shared: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CreatePasswordRequest<'a> {
+    pub ident: &'a str,
+    pub title: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documentation_url: Option<&'a str>,
+    pub password: &'a Secret,
+    pub owner: &'a str,
+    pub shared: &'a [String],
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UpdatePasswordRequest<'a> {
+    pub title: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documentation_url: Option<&'a str>,
+    pub password: &'a Secret,
+    pub owner: &'a str,
+    pub shared: &'a [String],
+}
+
+pub trait PasswordsApi {
+    fn passwords(&self) -> PasswordsClient<'_>;
+}
+
+pub struct PasswordsClient<'a>(&'a Client);
+
+impl PasswordsApi for Client {
+    fn passwords(&self) -> PasswordsClient<'_> {
+        PasswordsClient(self)
+    }
+}
+
+impl PasswordsClient<'_> {
+    pub fn create_password(
+        &self,
+        password: &CreatePasswordRequest,
+    ) -> Result<(ShowPasswordResponse, ETag)> {
+        self.0
+            .post_with_etag("/domain-types/password/collections/all", password)
+    }
+
+    pub fn update_password(
+        &self,
+        id: &str,
+        etag: ETag,
+        password: &UpdatePasswordRequest,
+    ) -> Result<(ShowPasswordResponse, ETag)> {
+        self.0
+            .put_if_match_with_etag(format!("/objects/password/{}", id), etag, password)
+    }
+
+    pub fn list_passwords(&self) -> Result<ListPasswordsResponse> {
+        self.0.get("/domain-types/password/collections/all")
+    }
+
+    pub fn show_password(&self, id: &str) -> Result<(ShowPasswordResponse, ETag)> {
+        self.0.get_with_etag(format!("/objects/password/{}", id))
+    }
+
+    pub fn delete_password(&self, id: &str) -> Result<()> {
+        self.0.delete(format!("/objects/password/{}", id))
+    }
+}
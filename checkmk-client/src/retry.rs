@@ -0,0 +1,311 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use reqwest::{
+    Method,
+    StatusCode,
+};
+use std::{
+    collections::hash_map::RandomState,
+    hash::{
+        BuildHasher,
+        Hasher,
+    },
+    time::Duration,
+};
+
+/// Configures how often and how fast a [`Client`](crate::Client) retries requests that failed
+/// transiently.
+///
+/// Only requests that are safe to repeat are retried after they reached the server: `GET`, `PUT`
+/// and `DELETE`. `POST` requests, including the ones carrying an `If-Match` header, are only retried
+/// if the connection to the server could not be established, i.e. they were never sent. Repeating
+/// a `POST` with `If-Match` that was already applied would fail with `412 Precondition Failed`,
+/// wrongly reporting that someone else modified the object.
+///
+/// A `DELETE` that is answered with `404 Not Found` after an earlier attempt may have reached the
+/// server is treated as successful, since the earlier attempt most likely deleted the object.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts for a single request, including the first one.
+    pub max_attempts: u32,
+    /// The time to wait before the first retry, doubled for every subsequent retry.
+    pub initial_backoff: Duration,
+    /// The upper bound for the time to wait between two attempts.
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries a request.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// The time to wait before the given retry, which is the exponential backoff reduced by a
+    /// random jitter of up to half of it.
+    pub(crate) fn backoff(&self, retry: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_backoff);
+        let jitter = RandomState::new().build_hasher().finish() % 1000;
+        backoff - backoff / 2 * jitter as u32 / 1000
+    }
+
+    pub(crate) fn should_retry_error(&self, idempotent: bool, error: &reqwest::Error) -> bool {
+        if error.is_connect() {
+            return true;
+        }
+        idempotent
+            && (error.is_timeout() || error.is_request())
+            && !error.is_builder()
+            && !error.is_decode()
+    }
+
+    pub(crate) fn should_retry_status(&self, idempotent: bool, status: StatusCode) -> bool {
+        idempotent
+            && matches!(
+                status,
+                StatusCode::TOO_MANY_REQUESTS
+                    | StatusCode::BAD_GATEWAY
+                    | StatusCode::SERVICE_UNAVAILABLE
+                    | StatusCode::GATEWAY_TIMEOUT
+            )
+    }
+}
+
+/// The attempts of sending a single request, deciding whether and when to retry it.
+///
+/// This is shared by the blocking and the asynchronous client, which only differ in how they send
+/// the request and wait between the attempts.
+pub(crate) struct Attempts<'a> {
+    policy: &'a RetryPolicy,
+    method: Method,
+    idempotent: bool,
+    attempt: u32,
+    earlier_attempt_sent: bool,
+}
+
+impl<'a> Attempts<'a> {
+    pub(crate) fn new(policy: &'a RetryPolicy, method: &Method) -> Self {
+        Self {
+            policy,
+            method: method.clone(),
+            idempotent: is_idempotent(method),
+            attempt: 1,
+            earlier_attempt_sent: false,
+        }
+    }
+
+    /// Whether the current attempt may be followed by another one.
+    pub(crate) fn retries_left(&self) -> bool {
+        self.attempt < self.policy.max_attempts
+    }
+
+    /// Decide whether to retry after the current attempt was answered with the status, returning
+    /// the time to wait before the next attempt.
+    pub(crate) fn retry_after_status(&mut self, status: StatusCode) -> Option<Duration> {
+        if !self.policy.should_retry_status(self.idempotent, status) {
+            return None;
+        }
+        self.earlier_attempt_sent = true;
+        Some(self.next_attempt())
+    }
+
+    /// Decide whether to retry after the current attempt failed with the error, returning the time
+    /// to wait before the next attempt.
+    pub(crate) fn retry_after_error(&mut self, error: &reqwest::Error) -> Option<Duration> {
+        if !self.policy.should_retry_error(self.idempotent, error) {
+            return None;
+        }
+        self.earlier_attempt_sent |= !error.is_connect();
+        Some(self.next_attempt())
+    }
+
+    /// Whether a `404 Not Found` answering the final attempt is caused by an earlier attempt that
+    /// reached the server and already deleted the object.
+    pub(crate) fn deleted_by_earlier_attempt(&self, status: StatusCode) -> bool {
+        deleted_by_earlier_attempt(&self.method, status, self.earlier_attempt_sent)
+    }
+
+    fn next_attempt(&mut self) -> Duration {
+        let backoff = self.policy.backoff(self.attempt);
+        self.attempt += 1;
+        backoff
+    }
+}
+
+/// Whether the request can be repeated without being applied twice, or failing because it was
+/// already applied.
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::PUT | Method::DELETE
+    )
+}
+
+/// Whether a `404 Not Found` answering a `DELETE` is caused by an earlier attempt that reached the
+/// server and already deleted the object.
+fn deleted_by_earlier_attempt(
+    method: &Method,
+    status: StatusCode,
+    earlier_attempt_sent: bool,
+) -> bool {
+    earlier_attempt_sent && *method == Method::DELETE && status == StatusCode::NOT_FOUND
+}
+
+/// The response to return instead of the `404 Not Found` of a repeated `DELETE`.
+pub(crate) fn deleted_response() -> http::Response<Vec<u8>> {
+    let mut response = http::Response::new(Vec::new());
+    *response.status_mut() = StatusCode::NO_CONTENT;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_requests_that_can_be_repeated_are_idempotent() {
+        assert!(is_idempotent(&Method::GET));
+        assert!(is_idempotent(&Method::PUT));
+        assert!(is_idempotent(&Method::DELETE));
+        assert!(!is_idempotent(&Method::POST));
+        assert!(!is_idempotent(&Method::PATCH));
+    }
+
+    #[test]
+    fn transient_status_codes_are_retried_for_idempotent_requests() {
+        let policy = RetryPolicy::default();
+        for status in [
+            StatusCode::TOO_MANY_REQUESTS,
+            StatusCode::BAD_GATEWAY,
+            StatusCode::SERVICE_UNAVAILABLE,
+            StatusCode::GATEWAY_TIMEOUT,
+        ] {
+            assert!(policy.should_retry_status(true, status));
+            assert!(!policy.should_retry_status(false, status));
+        }
+        for status in [
+            StatusCode::OK,
+            StatusCode::NOT_FOUND,
+            StatusCode::PRECONDITION_FAILED,
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ] {
+            assert!(!policy.should_retry_status(true, status));
+        }
+    }
+
+    #[test]
+    fn connection_errors_are_retried_for_all_requests() {
+        // Nothing listens on the port of a listener that was dropped again.
+        let address = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .unwrap();
+        let error = reqwest::blocking::Client::new()
+            .post(format!("http://{}", address))
+            .send()
+            .unwrap_err();
+        assert!(error.is_connect());
+
+        let policy = RetryPolicy::default();
+        assert!(policy.should_retry_error(true, &error));
+        assert!(policy.should_retry_error(false, &error));
+    }
+
+    #[test]
+    fn backoff_grows_exponentially_up_to_the_limit() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(1000),
+        };
+        for (retry, expected) in [(1, 100), (2, 200), (3, 400), (4, 800), (5, 1000), (9, 1000)] {
+            let backoff = policy.backoff(retry);
+            let expected = Duration::from_millis(expected);
+            // The jitter reduces the backoff by up to half of it.
+            assert!(
+                backoff <= expected && backoff >= expected / 2,
+                "backoff {:?} of retry {} is not within half of {:?}",
+                backoff,
+                retry,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn attempts_are_bounded_by_the_policy() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            ..Default::default()
+        };
+        let mut attempts = Attempts::new(&policy, &Method::GET);
+        assert!(attempts.retries_left());
+        assert!(attempts
+            .retry_after_status(StatusCode::SERVICE_UNAVAILABLE)
+            .is_some());
+        assert!(attempts.retries_left());
+        assert!(attempts
+            .retry_after_status(StatusCode::SERVICE_UNAVAILABLE)
+            .is_some());
+        assert!(!attempts.retries_left());
+        assert!(attempts.retry_after_status(StatusCode::OK).is_none());
+    }
+
+    #[test]
+    fn only_repeated_deletes_of_missing_objects_are_successful() {
+        assert!(deleted_by_earlier_attempt(
+            &Method::DELETE,
+            StatusCode::NOT_FOUND,
+            true
+        ));
+        assert!(!deleted_by_earlier_attempt(
+            &Method::DELETE,
+            StatusCode::NOT_FOUND,
+            false
+        ));
+        assert!(!deleted_by_earlier_attempt(
+            &Method::DELETE,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            true
+        ));
+        assert!(!deleted_by_earlier_attempt(
+            &Method::GET,
+            StatusCode::NOT_FOUND,
+            true
+        ));
+
+        let policy = RetryPolicy::default();
+        let mut attempts = Attempts::new(&policy, &Method::DELETE);
+        assert!(!attempts.deleted_by_earlier_attempt(StatusCode::NOT_FOUND));
+        attempts.retry_after_status(StatusCode::BAD_GATEWAY);
+        assert!(attempts.deleted_by_earlier_attempt(StatusCode::NOT_FOUND));
+    }
+}
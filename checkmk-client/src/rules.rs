@@ -49,6 +49,20 @@ pub struct ListRulesResponse {
     pub rules: Vec<ShowRuleResponse>,
 }
 
+#[derive(Clone, Debug, Deserialize)]
+pub struct ShowRulesetResponse {
+    /// The name of the ruleset.
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ListRulesetsResponse {
+    #[serde(rename = "value")]
+    pub rulesets: Vec<ShowRulesetResponse>,
+}
+
 #[derive(Clone, Debug, Serialize)]
 #[serde(tag = "position")]
 pub enum MoveToPositionRequest {
@@ -232,6 +246,14 @@ impl RulesClient<'_> {
             })
     }
 
+    /// List all rulesets that contain at least one rule, in any folder.
+    pub fn list_used_rulesets(&self) -> Result<ListRulesetsResponse> {
+        self.0
+            .get_with_action("/domain-types/ruleset/collections/all", |request_builder| {
+                request_builder.query(&[("used", "true")])
+            })
+    }
+
     pub fn delete_rule(&self, rule_id: &str) -> Result<()> {
         self.0.delete(format!("/objects/rule/{}", rule_id))
     }
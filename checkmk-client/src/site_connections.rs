@@ -0,0 +1,237 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{
+    Client,
+    Result,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ShowSiteConnectionResponse {
+    pub id: String,
+    pub extensions: SiteConfig,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ListSiteConnectionsResponse {
+    #[serde(rename = "value")]
+    pub site_connections: Vec<ShowSiteConnectionResponse>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SiteConnectionRequest<'a> {
+    pub site_config: &'a SiteConfig,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SiteConfig {
+    pub basic_settings: BasicSettings,
+    pub status_connection: StatusConnection,
+    pub configuration_connection: ConfigurationConnection,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BasicSettings {
+    pub site_id: String,
+    pub alias: String,
+}
+
+/// How the central site queries the status of the remote site through Livestatus.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StatusConnection {
+    pub connection: LivestatusConnection,
+    #[serde(default)]
+    pub proxy: LivestatusProxy,
+    /// The time to wait for the connection to be established, in seconds.
+    #[serde(default = "default_connect_timeout")]
+    pub connect_timeout: u32,
+    #[serde(default)]
+    pub persistent_connection: bool,
+    /// The URL prefix of the remote site's web interface, usually `/<site id>/`.
+    #[serde(default)]
+    pub url_prefix: String,
+    #[serde(default)]
+    pub status_host: StatusHost,
+    #[serde(default)]
+    pub disable_in_status_gui: bool,
+}
+
+fn default_connect_timeout() -> u32 {
+    2
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "socket_type", rename_all = "snake_case")]
+pub enum LivestatusConnection {
+    Local,
+    Unix {
+        path: String,
+    },
+    Tcp {
+        host: String,
+        port: u16,
+        #[serde(default = "default_true")]
+        encrypted: bool,
+        #[serde(default = "default_true")]
+        verify: bool,
+    },
+    Tcp6 {
+        host: String,
+        port: u16,
+        #[serde(default = "default_true")]
+        encrypted: bool,
+        #[serde(default = "default_true")]
+        verify: bool,
+    },
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LivestatusProxy {
+    pub use_livestatus_daemon: LivestatusDaemon,
+}
+
+impl Default for LivestatusProxy {
+    fn default() -> Self {
+        Self {
+            use_livestatus_daemon: LivestatusDaemon::Direct,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LivestatusDaemon {
+    Direct,
+    WithProxy,
+}
+
+/// The host on the central site that represents the remote site, if any.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status_host_set", rename_all = "snake_case")]
+pub enum StatusHost {
+    Enabled {
+        site: String,
+        host: String,
+    },
+    #[default]
+    Disabled,
+}
+
+/// How the central site distributes configuration to the remote site.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConfigurationConnection {
+    #[serde(default)]
+    pub enable_replication: bool,
+    #[serde(default)]
+    pub url_of_remote_site: String,
+    #[serde(default = "default_true")]
+    pub disable_remote_configuration: bool,
+    #[serde(default)]
+    pub ignore_tls_errors: bool,
+    #[serde(default = "default_true")]
+    pub direct_login_to_web_gui_allowed: bool,
+    #[serde(default)]
+    pub user_sync: UserSync,
+    #[serde(default = "default_true")]
+    pub replicate_event_console: bool,
+    #[serde(default)]
+    pub replicate_extensions: bool,
+}
+
+impl Default for ConfigurationConnection {
+    fn default() -> Self {
+        Self {
+            enable_replication: false,
+            url_of_remote_site: String::new(),
+            disable_remote_configuration: true,
+            ignore_tls_errors: false,
+            direct_login_to_web_gui_allowed: true,
+            user_sync: UserSync::default(),
+            replicate_event_console: true,
+            replicate_extensions: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "sync_with_ldap_connections", rename_all = "snake_case")]
+pub enum UserSync {
+    #[default]
+    All,
+    Disabled,
+    Ldap {
+        ldap_connections: Vec<String>,
+    },
+}
+
+pub trait SiteConnectionsApi {
+    fn site_connections(&self) -> SiteConnectionsClient<'_>;
+}
+
+pub struct SiteConnectionsClient<'a>(&'a Client);
+
+impl SiteConnectionsApi for Client {
+    fn site_connections(&self) -> SiteConnectionsClient<'_> {
+        SiteConnectionsClient(self)
+    }
+}
+
+impl SiteConnectionsClient<'_> {
+    pub fn create_site_connection(
+        &self,
+        site_config: &SiteConfig,
+    ) -> Result<ShowSiteConnectionResponse> {
+        self.0.post(
+            "/domain-types/site_connection/collections/all",
+            &SiteConnectionRequest { site_config },
+        )
+    }
+
+    pub fn update_site_connection(
+        &self,
+        site_id: &str,
+        site_config: &SiteConfig,
+    ) -> Result<ShowSiteConnectionResponse> {
+        self.0.put(
+            format!("/objects/site_connection/{}", site_id),
+            &SiteConnectionRequest { site_config },
+        )
+    }
+
+    /// List all site connections, including the one of the site itself.
+    pub fn list_site_connections(&self) -> Result<ListSiteConnectionsResponse> {
+        self.0.get("/domain-types/site_connection/collections/all")
+    }
+
+    pub fn show_site_connection(&self, site_id: &str) -> Result<ShowSiteConnectionResponse> {
+        self.0.get(format!("/objects/site_connection/{}", site_id))
+    }
+
+    pub fn delete_site_connection(&self, site_id: &str) -> Result<()> {
+        self.0.post_without_response(format!(
+            "/objects/site_connection/{}/actions/delete/invoke",
+            site_id
+        ))
+    }
+}
@@ -0,0 +1,169 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{
+    Client,
+    ETag,
+    Result,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ShowTimePeriodResponse {
+    pub id: String,
+    pub extensions: TimePeriodOutputExtensions,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ListTimePeriodsResponse {
+    #[serde(rename = "value")]
+    pub time_periods: Vec<ShowTimePeriodResponse>,
+}
+
+#[derive(Clone, Debug, PartialEq, Default, Deserialize)]
+pub struct TimePeriodOutputExtensions {
+    pub alias: String,
+    #[serde(default)]
+    pub active_time_ranges: Vec<ActiveTimeRange>,
+    #[serde(default)]
+    pub exceptions: Vec<TimePeriodException>,
+    /// The aliases of the time periods that are excluded from this time period.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ActiveTimeRange {
+    pub day: Weekday,
+    #[serde(default)]
+    pub time_ranges: Vec<TimeRange>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub enum Weekday {
+    #[serde(rename = "monday")]
+    Monday,
+    #[serde(rename = "tuesday")]
+    Tuesday,
+    #[serde(rename = "wednesday")]
+    Wednesday,
+    #[serde(rename = "thursday")]
+    Thursday,
+    #[serde(rename = "friday")]
+    Friday,
+    #[serde(rename = "saturday")]
+    Saturday,
+    #[serde(rename = "sunday")]
+    Sunday,
+    /// Shorthand for every day of the week, which Checkmk expands into the individual days.
+    #[serde(rename = "all")]
+    All,
+}
+
+impl Weekday {
+    pub const DAYS: [Weekday; 7] = [
+        Self::Monday,
+        Self::Tuesday,
+        Self::Wednesday,
+        Self::Thursday,
+        Self::Friday,
+        Self::Saturday,
+        Self::Sunday,
+    ];
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+pub struct TimeRange {
+    /// The start of the range, in the format `HH:MM`.
+    pub start: String,
+    /// The end of the range, in the format `HH:MM`.
+    pub end: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct TimePeriodException {
+    /// The date of the exception, in the format `YYYY-MM-DD`.
+    pub date: String,
+    #[serde(default)]
+    pub time_ranges: Vec<TimeRange>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CreateTimePeriodRequest<'a> {
+    pub name: &'a str,
+    pub alias: &'a str,
+    pub active_time_ranges: &'a [ActiveTimeRange],
+    pub exceptions: &'a [TimePeriodException],
+    /// The aliases of the time periods that are excluded from this time period.
+    pub exclude: Vec<&'a str>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UpdateTimePeriodRequest<'a> {
+    pub alias: &'a str,
+    pub active_time_ranges: &'a [ActiveTimeRange],
+    pub exceptions: &'a [TimePeriodException],
+    /// The aliases of the time periods that are excluded from this time period.
+    pub exclude: Vec<&'a str>,
+}
+
+pub trait TimePeriodsApi {
+    fn time_periods(&self) -> TimePeriodsClient<'_>;
+}
+
+pub struct TimePeriodsClient<'a>(&'a Client);
+
+impl TimePeriodsApi for Client {
+    fn time_periods(&self) -> TimePeriodsClient<'_> {
+        TimePeriodsClient(self)
+    }
+}
+
+impl TimePeriodsClient<'_> {
+    pub fn create_time_period(
+        &self,
+        time_period: &CreateTimePeriodRequest,
+    ) -> Result<(ShowTimePeriodResponse, ETag)> {
+        self.0
+            .post_with_etag("/domain-types/time_period/collections/all", time_period)
+    }
+
+    pub fn update_time_period(
+        &self,
+        name: &str,
+        etag: ETag,
+        time_period: &UpdateTimePeriodRequest,
+    ) -> Result<(ShowTimePeriodResponse, ETag)> {
+        self.0
+            .put_if_match_with_etag(format!("/objects/time_period/{}", name), etag, time_period)
+    }
+
+    pub fn list_time_periods(&self) -> Result<ListTimePeriodsResponse> {
+        self.0.get("/domain-types/time_period/collections/all")
+    }
+
+    pub fn show_time_period(&self, name: &str) -> Result<(ShowTimePeriodResponse, ETag)> {
+        self.0
+            .get_with_etag(format!("/objects/time_period/{}", name))
+    }
+
+    pub fn delete_time_period(&self, name: &str) -> Result<()> {
+        self.0.delete(format!("/objects/time_period/{}", name))
+    }
+}
@@ -0,0 +1,99 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The layer that sends the requests of a [`Client`](crate::Client) and receives the responses.
+//!
+//! By default requests are sent with reqwest, but any [`Transport`] can be configured through
+//! [`ClientBuilder::transport`](crate::ClientBuilder::transport), for example to talk to a fake
+//! backend. [`Middleware`] can be added with
+//! [`ClientBuilder::middleware`](crate::ClientBuilder::middleware) to inspect or modify requests
+//! and responses, e.g. for logging, metrics or signing requests.
+
+use crate::Result;
+use reqwest::blocking::{
+    Request,
+    Response,
+};
+use std::sync::Arc;
+
+/// Sends a request and returns the response, regardless of its status code.
+pub trait Transport: Send + Sync {
+    fn send(&self, request: Request) -> Result<Response>;
+}
+
+impl Transport for reqwest::blocking::Client {
+    fn send(&self, request: Request) -> Result<Response> {
+        self.execute(request).map_err(Into::into)
+    }
+}
+
+impl<F> Transport for F
+where
+    F: Fn(Request) -> Result<Response> + Send + Sync,
+{
+    fn send(&self, request: Request) -> Result<Response> {
+        self(request)
+    }
+}
+
+/// A layer around the transport, which can inspect or modify the request before passing it on to
+/// the next layer, and the response before returning it.
+///
+/// Middleware is run for every attempt of a request, i.e. once more for every retry.
+pub trait Middleware: Send + Sync {
+    fn handle(&self, request: Request, next: Next<'_>) -> Result<Response>;
+}
+
+impl<F> Middleware for F
+where
+    F: Fn(Request, Next<'_>) -> Result<Response> + Send + Sync,
+{
+    fn handle(&self, request: Request, next: Next<'_>) -> Result<Response> {
+        self(request, next)
+    }
+}
+
+/// The remaining middleware and the transport a request is passed on to.
+pub struct Next<'a> {
+    middlewares: &'a [Arc<dyn Middleware>],
+    transport: &'a dyn Transport,
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(
+        middlewares: &'a [Arc<dyn Middleware>],
+        transport: &'a dyn Transport,
+    ) -> Self {
+        Self {
+            middlewares,
+            transport,
+        }
+    }
+
+    /// Pass the request on to the next middleware, or the transport if there is none left.
+    pub fn run(self, request: Request) -> Result<Response> {
+        match self.middlewares.split_first() {
+            Some((middleware, middlewares)) => middleware.handle(
+                request,
+                Next {
+                    middlewares,
+                    transport: self.transport,
+                },
+            ),
+            None => self.transport.send(request),
+        }
+    }
+}
@@ -0,0 +1,193 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{
+    Client,
+    ETag,
+    Result,
+};
+use crate::models::{
+    ExtraAttributes,
+    Secret,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ShowUserResponse {
+    pub id: String,
+    pub extensions: UserOutputExtensions,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ListUsersResponse {
+    #[serde(rename = "value")]
+    pub users: Vec<ShowUserResponse>,
+}
+
+#[derive(Clone, Debug, PartialEq, Default, Deserialize)]
+pub struct UserOutputExtensions {
+    #[serde(default)]
+    pub fullname: String,
+    #[serde(default)]
+    pub disable_login: bool,
+    pub contact_options: Option<ContactOptions>,
+    pub pager_address: Option<String>,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(rename = "contactgroups", default)]
+    pub contact_groups: Vec<String>,
+    pub disable_notifications: Option<DisableNotifications>,
+    pub auth_option: Option<AuthOptionOutput>,
+    /// All other attributes of the user, including custom user attributes.
+    #[serde(flatten, default)]
+    pub extra_attributes: ExtraAttributes,
+}
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct ContactOptions {
+    #[serde(default)]
+    pub email: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback_contact: Option<bool>,
+}
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct DisableNotifications {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disable: Option<bool>,
+}
+
+/// How a user authenticates, as reported by Checkmk. The secrets themselves are never reported.
+#[derive(Clone, Debug, PartialEq, Default, Deserialize)]
+pub struct AuthOptionOutput {
+    pub auth_type: Option<AuthType>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub enum AuthType {
+    #[serde(rename = "password")]
+    Password,
+    #[serde(rename = "automation")]
+    Automation,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "auth_type")]
+pub enum AuthOption {
+    #[serde(rename = "password")]
+    Password { password: Secret },
+    #[serde(rename = "automation")]
+    Automation { secret: Secret },
+}
+
+impl AuthOption {
+    pub fn auth_type(&self) -> AuthType {
+        match self {
+            Self::Password { .. } => AuthType::Password,
+            Self::Automation { .. } => AuthType::Automation,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CreateUserRequest<'a> {
+    pub username: &'a str,
+    pub fullname: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth_option: Option<AuthOption>,
+    pub disable_login: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_options: Option<ContactOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pager_address: Option<&'a str>,
+    pub roles: &'a [String],
+    #[serde(rename = "contactgroups")]
+    pub contact_groups: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disable_notifications: Option<DisableNotifications>,
+    /// Custom user attributes, which have to be defined on the site.
+    #[serde(flatten, skip_serializing_if = "ExtraAttributes::is_empty")]
+    pub extra_attributes: ExtraAttributes,
+}
+
+/// Updates a user. Only the fields which are set are changed.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct UpdateUserRequest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fullname: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth_option: Option<AuthOption>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disable_login: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_options: Option<ContactOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pager_address: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub roles: Option<&'a [String]>,
+    #[serde(rename = "contactgroups", skip_serializing_if = "Option::is_none")]
+    pub contact_groups: Option<&'a [String]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disable_notifications: Option<DisableNotifications>,
+    /// Custom user attributes, which have to be defined on the site.
+    #[serde(flatten, skip_serializing_if = "ExtraAttributes::is_empty")]
+    pub extra_attributes: ExtraAttributes,
+}
+
+pub trait UsersApi {
+    fn users(&self) -> UsersClient<'_>;
+}
+
+pub struct UsersClient<'a>(&'a Client);
+
+impl UsersApi for Client {
+    fn users(&self) -> UsersClient<'_> {
+        UsersClient(self)
+    }
+}
+
+impl UsersClient<'_> {
+    pub fn create_user(&self, user: &CreateUserRequest) -> Result<(ShowUserResponse, ETag)> {
+        self.0
+            .post_with_etag("/domain-types/user_config/collections/all", user)
+    }
+
+    pub fn update_user(
+        &self,
+        username: &str,
+        etag: ETag,
+        user: &UpdateUserRequest,
+    ) -> Result<(ShowUserResponse, ETag)> {
+        self.0
+            .put_if_match_with_etag(format!("/objects/user_config/{}", username), etag, user)
+    }
+
+    pub fn list_users(&self) -> Result<ListUsersResponse> {
+        self.0.get("/domain-types/user_config/collections/all")
+    }
+
+    pub fn show_user(&self, username: &str) -> Result<(ShowUserResponse, ETag)> {
+        self.0
+            .get_with_etag(format!("/objects/user_config/{}", username))
+    }
+
+    pub fn delete_user(&self, username: &str) -> Result<()> {
+        self.0.delete(format!("/objects/user_config/{}", username))
+    }
+}
@@ -0,0 +1,953 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The subset of the checkmk REST API that checkmate uses, backed by objects kept in memory.
+
+use crate::http::{
+    Request,
+    Response,
+};
+use serde_json::{
+    json,
+    Value,
+};
+use std::collections::{
+    BTreeMap,
+    BTreeSet,
+};
+
+/// An object that is modified through its ETag.
+#[derive(Debug)]
+struct Object {
+    etag: String,
+    value: Value,
+}
+
+#[derive(Debug)]
+struct Rule {
+    id: String,
+    ruleset: String,
+    folder: String,
+    value: Value,
+}
+
+/// A type of object that is stored with the fields of its requests as its extensions.
+struct DomainType {
+    name: &'static str,
+    /// The human readable name, used to describe changes.
+    kind: &'static str,
+    /// The field of the create request containing the ID of the object.
+    id_field: &'static str,
+    /// The field of the requests containing the title of the object.
+    title_field: &'static str,
+    /// Turn the fields of a request into the extensions checkmk reports for the object.
+    extensions: fn(Value) -> Value,
+}
+
+const DOMAIN_TYPES: &[DomainType] = &[
+    DomainType {
+        name: "user_config",
+        kind: "user",
+        id_field: "username",
+        title_field: "fullname",
+        extensions: without_secrets,
+    },
+    DomainType {
+        name: "host_group_config",
+        kind: "host group",
+        id_field: "name",
+        title_field: "alias",
+        extensions: std::convert::identity,
+    },
+    DomainType {
+        name: "service_group_config",
+        kind: "service group",
+        id_field: "name",
+        title_field: "alias",
+        extensions: std::convert::identity,
+    },
+    DomainType {
+        name: "contact_group_config",
+        kind: "contact group",
+        id_field: "name",
+        title_field: "alias",
+        extensions: std::convert::identity,
+    },
+    DomainType {
+        name: "time_period",
+        kind: "time period",
+        id_field: "name",
+        title_field: "alias",
+        extensions: time_period_extensions,
+    },
+    DomainType {
+        name: "password",
+        kind: "password",
+        id_field: "ident",
+        title_field: "title",
+        extensions: password_extensions,
+    },
+    DomainType {
+        name: "aux_tag",
+        kind: "aux tag",
+        id_field: "aux_tag_id",
+        title_field: "title",
+        extensions: std::convert::identity,
+    },
+    DomainType {
+        name: "host_tag_group",
+        kind: "host tag group",
+        id_field: "ident",
+        title_field: "title",
+        extensions: host_tag_group_extensions,
+    },
+];
+
+fn domain_type(name: &str) -> Option<&'static DomainType> {
+    DOMAIN_TYPES
+        .iter()
+        .find(|domain_type| domain_type.name == name)
+}
+
+/// Remove the secrets from the fields of a request, since checkmk never reports them.
+fn without_secrets(mut fields: Value) -> Value {
+    if let Some(auth_option) = fields.get_mut("auth_option") {
+        *auth_option = json!({"auth_type": auth_option["auth_type"]});
+    }
+    fields
+}
+
+/// Checkmk never reports stored passwords, and reports their owner as `owned_by`.
+fn password_extensions(mut fields: Value) -> Value {
+    if let Some(fields) = fields.as_object_mut() {
+        fields.remove("password");
+        if let Some(owner) = fields.remove("owner") {
+            fields.insert("owned_by".to_owned(), owner);
+        }
+    }
+    fields
+}
+
+/// Checkmk expands the active time ranges of `all` days into the individual days, and reports
+/// times with seconds.
+fn time_period_extensions(mut fields: Value) -> Value {
+    const DAYS: [&str; 7] = [
+        "monday",
+        "tuesday",
+        "wednesday",
+        "thursday",
+        "friday",
+        "saturday",
+        "sunday",
+    ];
+
+    fn with_seconds(time_ranges: &mut Value) {
+        for time_range in time_ranges.as_array_mut().into_iter().flatten() {
+            for field in ["start", "end"] {
+                if let Some(time) = time_range[field].as_str().filter(|time| time.len() == 5) {
+                    time_range[field] = json!(format!("{}:00", time));
+                }
+            }
+        }
+    }
+
+    if let Some(active_time_ranges) = fields["active_time_ranges"].as_array_mut() {
+        let mut expanded = Vec::new();
+        for mut active_time_range in active_time_ranges.drain(..) {
+            with_seconds(&mut active_time_range["time_ranges"]);
+            if active_time_range["day"] == "all" {
+                for day in DAYS {
+                    let mut active_time_range = active_time_range.clone();
+                    active_time_range["day"] = json!(day);
+                    expanded.push(active_time_range);
+                }
+            } else {
+                expanded.push(active_time_range);
+            }
+        }
+        *active_time_ranges = expanded;
+    }
+    for exception in fields["exceptions"].as_array_mut().into_iter().flatten() {
+        with_seconds(&mut exception["time_ranges"]);
+    }
+    fields
+}
+
+/// Tags are identified by `ident` in requests, but by `id` in responses, and whether to repair
+/// usages of removed tags is not part of the tag group.
+fn host_tag_group_extensions(mut fields: Value) -> Value {
+    if let Some(fields) = fields.as_object_mut() {
+        fields.remove("repair");
+    }
+    for tag in fields["tags"].as_array_mut().into_iter().flatten() {
+        if let Some(tag) = tag.as_object_mut() {
+            if let Some(id) = tag.remove("ident") {
+                tag.insert("id".to_owned(), id);
+            }
+        }
+    }
+    fields
+}
+
+#[derive(Debug)]
+pub(crate) struct State {
+    folders: BTreeMap<String, Object>,
+    hosts: BTreeMap<String, Object>,
+    /// Objects of the [`DOMAIN_TYPES`], by their domain type and ID.
+    objects: BTreeMap<(String, String), Object>,
+    rules: Vec<Rule>,
+    pending_changes: Vec<Value>,
+    /// Every change made through the API, which is kept after activating the changes.
+    changes: Vec<String>,
+    activations: usize,
+    revision: u64,
+    conflict_on_next_update: bool,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        let mut state = Self {
+            folders: BTreeMap::new(),
+            hosts: BTreeMap::new(),
+            objects: BTreeMap::new(),
+            rules: Vec::new(),
+            pending_changes: Vec::new(),
+            changes: Vec::new(),
+            activations: 0,
+            revision: 0,
+            conflict_on_next_update: false,
+        };
+        let etag = state.next_etag();
+        state.folders.insert(
+            "/".to_owned(),
+            Object {
+                etag,
+                value: json!({"title": "Main", "attributes": {}}),
+            },
+        );
+        state
+    }
+}
+
+/// Turn a folder identifier, like `~a~b` or `/a/b`, into its path, like `/a/b`.
+fn folder_path(identifier: &str) -> String {
+    let path = identifier.replace('~', "/");
+    let path = path.trim_end_matches('/');
+    if path.starts_with('/') {
+        path.to_owned()
+    } else {
+        format!("/{}", path)
+    }
+}
+
+fn folder_id(path: &str) -> String {
+    if path == "/" {
+        "~".to_owned()
+    } else {
+        path.replace('/', "~")
+    }
+}
+
+fn not_found(kind: &str, id: &str) -> Response {
+    Response::problem(
+        404,
+        "Not Found",
+        format!("{} {:?} does not exist", kind, id),
+    )
+}
+
+fn bad_request<S: Into<String>>(detail: S) -> Response {
+    Response::problem(400, "Bad Request", detail)
+}
+
+fn not_implemented(request: &Request, endpoint: &str) -> Response {
+    Response::problem(
+        501,
+        "Not Implemented",
+        format!(
+            "{} {} is not supported by the fake checkmk server",
+            request.method, endpoint
+        ),
+    )
+}
+
+impl State {
+    pub(crate) fn handle(&mut self, request: &Request, endpoint: &str) -> Response {
+        let body = if request.body.is_empty() {
+            Value::Null
+        } else {
+            match serde_json::from_slice(&request.body) {
+                Ok(body) => body,
+                Err(error) => return bad_request(format!("invalid JSON body: {}", error)),
+            }
+        };
+        let segments = endpoint.trim_matches('/').split('/').collect::<Vec<_>>();
+        match (request.method.as_str(), segments.as_slice()) {
+            (
+                "GET",
+                ["domain-types", "site_connection" | "notification_rule", "collections", "all"],
+            ) => Response::json(200, json!({"value": []})),
+
+            ("GET", ["objects", "folder_config", id]) => self.show_folder(id),
+            ("POST", ["domain-types", "folder_config", "collections", "all"]) => {
+                self.create_folder(&body)
+            }
+            ("PUT", ["objects", "folder_config", id]) => self.update_folder(request, id, &body),
+
+            ("GET", ["objects", "host_config", host_name]) => self.show_host(host_name),
+            ("POST", ["domain-types", "host_config", "collections", "all"]) => {
+                self.create_host(&body, false)
+            }
+            ("POST", ["domain-types", "host_config", "collections", "clusters"]) => {
+                self.create_host(&body, true)
+            }
+            ("PUT", ["objects", "host_config", host_name]) => {
+                self.update_host(request, host_name, &body)
+            }
+            ("PUT", ["objects", "host_config", host_name, "properties", "nodes"]) => {
+                self.update_nodes(request, host_name, &body)
+            }
+            ("POST", ["objects", "host_config", host_name, "actions", "move", "invoke"]) => {
+                self.move_host(request, host_name, &body)
+            }
+
+            ("GET", ["domain-types", "rule", "collections", "all"]) => {
+                match request.query.get("ruleset_name") {
+                    Some(ruleset) => self.list_rules(ruleset),
+                    None => bad_request("ruleset_name is required"),
+                }
+            }
+            ("POST", ["domain-types", "rule", "collections", "all"]) => self.create_rule(&body),
+            ("GET", ["domain-types", "ruleset", "collections", "all"]) => {
+                match request.query.get("used").map(String::as_str) {
+                    Some("true") => self.list_used_rulesets(),
+                    _ => not_implemented(request, endpoint),
+                }
+            }
+            ("GET", ["objects", "rule", id]) => self.show_rule(id),
+            ("DELETE", ["objects", "rule", id]) => self.delete_rule(id),
+
+            ("GET", ["domain-types", name, "collections", "all"])
+                if domain_type(name).is_some() =>
+            {
+                self.list_objects(name)
+            }
+            ("POST", ["domain-types", name, "collections", "all"]) => match domain_type(name) {
+                Some(domain_type) => self.create_object(domain_type, &body),
+                None => not_implemented(request, endpoint),
+            },
+            ("GET", ["objects", name, id]) if domain_type(name).is_some() => {
+                self.show_object(name, id)
+            }
+            ("PUT", ["objects", "host_tag_group", id]) => {
+                self.update_host_tag_group(request, id, &body)
+            }
+            ("PUT", ["objects", name, id]) => match domain_type(name) {
+                Some(domain_type) => self.update_object(request, domain_type, id, &body),
+                None => not_implemented(request, endpoint),
+            },
+            ("DELETE", ["objects", name, id]) => match domain_type(name) {
+                Some(domain_type) => self.delete_object(domain_type, id),
+                None => not_implemented(request, endpoint),
+            },
+
+            ("GET", ["domain-types", "activation_run", "collections", "pending_changes"]) => {
+                Response::json(200, json!({"value": self.pending_changes}))
+                    .with_etag(&self.pending_changes_etag())
+            }
+            (
+                "POST",
+                ["domain-types", "activation_run", "actions", "activate-changes", "invoke"],
+            ) => self.activate_changes(request),
+            (
+                "GET",
+                ["objects", "activation_run", _, "actions", "wait-for-completion", "invoke"],
+            ) => Response::no_content(),
+
+            _ => not_implemented(request, endpoint),
+        }
+    }
+
+    pub(crate) fn changes(&self) -> &[String] {
+        &self.changes
+    }
+
+    pub(crate) fn take_changes(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.changes)
+    }
+
+    pub(crate) fn activations(&self) -> usize {
+        self.activations
+    }
+
+    pub(crate) fn conflict_on_next_update(&mut self) {
+        self.conflict_on_next_update = true;
+    }
+
+    pub(crate) fn folder(&self, path: &str) -> Option<Value> {
+        let path = folder_path(path);
+        self.folders
+            .get(&path)
+            .map(|folder| Self::folder_response(&path, folder))
+    }
+
+    pub(crate) fn host(&self, host_name: &str) -> Option<Value> {
+        self.hosts
+            .get(host_name)
+            .map(|host| Self::host_response(host_name, host))
+    }
+
+    pub(crate) fn rules(&self, ruleset: &str) -> Vec<Value> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.ruleset == ruleset)
+            .map(|rule| self.rule_response(rule))
+            .collect()
+    }
+
+    pub(crate) fn object(&self, domain_type: &str, id: &str) -> Option<Value> {
+        self.objects
+            .get(&(domain_type.to_owned(), id.to_owned()))
+            .map(|object| object.value.clone())
+    }
+
+    /// Replace the attributes of a host, like a user editing it in the UI would.
+    pub(crate) fn set_host_attributes(&mut self, host_name: &str, attributes: Value) {
+        let etag = self.next_etag();
+        let host = self
+            .hosts
+            .get_mut(host_name)
+            .unwrap_or_else(|| panic!("host {:?} does not exist", host_name));
+        host.etag = etag;
+        host.value["attributes"] = attributes;
+        self.record_change(
+            "edit-host",
+            format!("Modified attributes of host {}", host_name),
+        );
+    }
+
+    fn next_etag(&mut self) -> String {
+        self.revision += 1;
+        format!("\"{:016x}\"", self.revision)
+    }
+
+    fn record_change(&mut self, action_name: &str, text: String) {
+        self.pending_changes.push(json!({
+            "id": format!("{:08x}", self.revision),
+            "user_id": "automation",
+            "action_name": action_name,
+            "text": text,
+            "time": "2024-01-01T00:00:00+00:00",
+        }));
+        self.changes.push(text);
+    }
+
+    fn pending_changes_etag(&self) -> String {
+        format!("\"{}-{}\"", self.activations, self.pending_changes.len())
+    }
+
+    /// Verify the `If-Match` header of the request against the current ETag of the object.
+    fn check_if_match(&mut self, request: &Request, etag: &str) -> Result<(), Response> {
+        let Some(if_match) = request.headers.get("if-match") else {
+            return Err(Response::problem(
+                428,
+                "Precondition Required",
+                "If-Match header is missing",
+            ));
+        };
+        if std::mem::take(&mut self.conflict_on_next_update) || if_match != etag {
+            return Err(Response::problem(
+                412,
+                "Precondition Failed",
+                "The ETag of the object does not match the If-Match header",
+            ));
+        }
+        Ok(())
+    }
+
+    fn folder_response(path: &str, folder: &Object) -> Value {
+        json!({
+            "id": folder_id(path),
+            "title": folder.value["title"],
+            "extensions": {
+                "path": path,
+                "attributes": folder.value["attributes"],
+            },
+        })
+    }
+
+    fn show_folder(&self, id: &str) -> Response {
+        let path = folder_path(id);
+        match self.folders.get(&path) {
+            Some(folder) => {
+                Response::json(200, Self::folder_response(&path, folder)).with_etag(&folder.etag)
+            }
+            None => not_found("Folder", id),
+        }
+    }
+
+    fn create_folder(&mut self, body: &Value) -> Response {
+        let (Some(name), Some(title), Some(parent)) = (
+            body["name"].as_str(),
+            body["title"].as_str(),
+            body["parent"].as_str(),
+        ) else {
+            return bad_request("name, title and parent are required");
+        };
+        let parent = folder_path(parent);
+        if !self.folders.contains_key(&parent) {
+            return bad_request(format!("parent folder {} does not exist", parent));
+        }
+        let path = format!("{}/{}", parent.trim_end_matches('/'), name);
+        if self.folders.contains_key(&path) {
+            return bad_request(format!("folder {} already exists", path));
+        }
+        let folder = Object {
+            etag: self.next_etag(),
+            value: json!({
+                "title": title,
+                "attributes": body.get("attributes").cloned().unwrap_or_else(|| json!({})),
+            }),
+        };
+        let response =
+            Response::json(200, Self::folder_response(&path, &folder)).with_etag(&folder.etag);
+        self.folders.insert(path.clone(), folder);
+        self.record_change("new-folder", format!("Created new folder {}", path));
+        response
+    }
+
+    fn update_folder(&mut self, request: &Request, id: &str, body: &Value) -> Response {
+        let path = folder_path(id);
+        let Some(etag) = self.folders.get(&path).map(|folder| folder.etag.clone()) else {
+            return not_found("Folder", id);
+        };
+        if let Err(response) = self.check_if_match(request, &etag) {
+            return response;
+        }
+        let etag = self.next_etag();
+        let folder = self.folders.get_mut(&path).expect("folder exists");
+        folder.etag = etag;
+        if let Some(title) = body.get("title") {
+            folder.value["title"] = title.clone();
+        }
+        if let Some(attributes) = body.get("attributes") {
+            folder.value["attributes"] = attributes.clone();
+        }
+        let response =
+            Response::json(200, Self::folder_response(&path, folder)).with_etag(&folder.etag);
+        self.record_change("edit-folder", format!("Modified folder {}", path));
+        response
+    }
+
+    fn host_response(host_name: &str, host: &Object) -> Value {
+        json!({
+            "id": host_name,
+            "title": host_name,
+            "extensions": {
+                "folder": host.value["folder"],
+                "attributes": host.value["attributes"],
+                "is_cluster": host.value["nodes"].is_array(),
+                "is_offline": false,
+                "cluster_nodes": host.value["nodes"],
+            },
+        })
+    }
+
+    fn show_host(&self, host_name: &str) -> Response {
+        match self.hosts.get(host_name) {
+            Some(host) => {
+                Response::json(200, Self::host_response(host_name, host)).with_etag(&host.etag)
+            }
+            None => not_found("Host", host_name),
+        }
+    }
+
+    fn create_host(&mut self, body: &Value, is_cluster: bool) -> Response {
+        let (Some(host_name), Some(folder)) = (body["host_name"].as_str(), body["folder"].as_str())
+        else {
+            return bad_request("host_name and folder are required");
+        };
+        let folder = folder_path(folder);
+        if !self.folders.contains_key(&folder) {
+            return bad_request(format!("folder {} does not exist", folder));
+        }
+        if self.hosts.contains_key(host_name) {
+            return bad_request(format!("host {} already exists", host_name));
+        }
+        let nodes = if is_cluster {
+            if let Some(node) = body["nodes"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .find(|node| !self.hosts.contains_key(*node))
+            {
+                return bad_request(format!("node {} does not exist", node));
+            }
+            body["nodes"].clone()
+        } else {
+            Value::Null
+        };
+        let host = Object {
+            etag: self.next_etag(),
+            value: json!({
+                "folder": folder,
+                "attributes": body.get("attributes").cloned().unwrap_or_else(|| json!({})),
+                "nodes": nodes,
+            }),
+        };
+        let response =
+            Response::json(200, Self::host_response(host_name, &host)).with_etag(&host.etag);
+        self.hosts.insert(host_name.to_owned(), host);
+        self.record_change("create-host", format!("Created new host {}", host_name));
+        response
+    }
+
+    /// Verify the ETag of the host and update it, returning the host as the response.
+    fn modify_host<F: FnOnce(&mut Value) -> Result<(), Response>>(
+        &mut self,
+        request: &Request,
+        host_name: &str,
+        change: &str,
+        modify: F,
+    ) -> Response {
+        let Some(etag) = self.hosts.get(host_name).map(|host| host.etag.clone()) else {
+            return not_found("Host", host_name);
+        };
+        if let Err(response) = self.check_if_match(request, &etag) {
+            return response;
+        }
+        let mut value = self.hosts[host_name].value.clone();
+        if let Err(response) = modify(&mut value) {
+            return response;
+        }
+        let etag = self.next_etag();
+        let host = self.hosts.get_mut(host_name).expect("host exists");
+        host.etag = etag;
+        host.value = value;
+        let response =
+            Response::json(200, Self::host_response(host_name, host)).with_etag(&host.etag);
+        self.record_change("edit-host", format!("{} {}", change, host_name));
+        response
+    }
+
+    fn update_host(&mut self, request: &Request, host_name: &str, body: &Value) -> Response {
+        self.modify_host(request, host_name, "Modified attributes of host", |host| {
+            if let Some(attributes) = body.get("attributes") {
+                host["attributes"] = attributes.clone();
+            }
+            Ok(())
+        })
+    }
+
+    fn update_nodes(&mut self, request: &Request, host_name: &str, body: &Value) -> Response {
+        let response = self.modify_host(request, host_name, "Modified nodes of host", |host| {
+            if !host["nodes"].is_array() {
+                return Err(bad_request(format!("host {} is not a cluster", host_name)));
+            }
+            host["nodes"] = body["nodes"].clone();
+            Ok(())
+        });
+        if response.status != 200 {
+            return response;
+        }
+        Response::json(200, json!({"value": body["nodes"]}))
+    }
+
+    fn move_host(&mut self, request: &Request, host_name: &str, body: &Value) -> Response {
+        let Some(target_folder) = body["target_folder"].as_str().map(folder_path) else {
+            return bad_request("target_folder is required");
+        };
+        if !self.folders.contains_key(&target_folder) {
+            return bad_request(format!("folder {} does not exist", target_folder));
+        }
+        self.modify_host(request, host_name, "Moved host", |host| {
+            host["folder"] = json!(target_folder);
+            Ok(())
+        })
+    }
+
+    fn list_objects(&self, name: &str) -> Response {
+        let objects = self
+            .objects
+            .iter()
+            .filter(|((domain_type, _), _)| domain_type == name)
+            .map(|(_, object)| object.value.clone())
+            .collect::<Vec<_>>();
+        Response::json(200, json!({"value": objects}))
+    }
+
+    fn show_object(&self, name: &str, id: &str) -> Response {
+        match self.objects.get(&(name.to_owned(), id.to_owned())) {
+            Some(object) => Response::json(200, object.value.clone()).with_etag(&object.etag),
+            None => not_found(name, id),
+        }
+    }
+
+    fn create_object(&mut self, domain_type: &DomainType, body: &Value) -> Response {
+        let Some(id) = body[domain_type.id_field].as_str() else {
+            return bad_request(format!("{} is required", domain_type.id_field));
+        };
+        let key = (domain_type.name.to_owned(), id.to_owned());
+        if self.objects.contains_key(&key) {
+            return bad_request(format!("{} {} already exists", domain_type.kind, id));
+        }
+        let mut extensions = (domain_type.extensions)(body.clone());
+        if let Some(fields) = extensions.as_object_mut() {
+            fields.remove(domain_type.id_field);
+        }
+        let object = Object {
+            etag: self.next_etag(),
+            value: json!({
+                "id": id,
+                "title": body[domain_type.title_field],
+                "extensions": extensions,
+            }),
+        };
+        let response = Response::json(200, object.value.clone()).with_etag(&object.etag);
+        self.objects.insert(key, object);
+        self.record_change(
+            &format!("new-{}", domain_type.kind),
+            format!("Created new {} {}", domain_type.kind, id),
+        );
+        response
+    }
+
+    fn update_object(
+        &mut self,
+        request: &Request,
+        domain_type: &DomainType,
+        id: &str,
+        body: &Value,
+    ) -> Response {
+        let key = (domain_type.name.to_owned(), id.to_owned());
+        let Some(etag) = self.objects.get(&key).map(|object| object.etag.clone()) else {
+            return not_found(domain_type.kind, id);
+        };
+        if let Err(response) = self.check_if_match(request, &etag) {
+            return response;
+        }
+        let etag = self.next_etag();
+        let object = self.objects.get_mut(&key).expect("object exists");
+        object.etag = etag;
+        if let Value::Object(fields) = (domain_type.extensions)(body.clone()) {
+            for (field, value) in fields {
+                if field == domain_type.title_field {
+                    object.value["title"] = value.clone();
+                }
+                object.value["extensions"][field] = value;
+            }
+        }
+        let response = Response::json(200, object.value.clone()).with_etag(&object.etag);
+        self.record_change(
+            &format!("edit-{}", domain_type.kind),
+            format!("Modified {} {}", domain_type.kind, id),
+        );
+        response
+    }
+
+    /// Update a host tag group, refusing to remove tags that are still used by hosts or folders
+    /// unless asked to repair them, like checkmk does.
+    fn update_host_tag_group(&mut self, request: &Request, id: &str, body: &Value) -> Response {
+        let domain_type = domain_type("host_tag_group").expect("host tag groups are supported");
+        let Some(tag_group) = self.object(domain_type.name, id) else {
+            return not_found(domain_type.kind, id);
+        };
+        let declared_tags = body["tags"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|tag| &tag["ident"])
+            .collect::<Vec<_>>();
+        let attribute = format!("tag_{}", id);
+        let removed_tag_in_use = tag_group["extensions"]["tags"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|tag| &tag["id"])
+            .filter(|tag_id| !declared_tags.contains(tag_id))
+            .any(|tag_id| {
+                self.folders
+                    .values()
+                    .chain(self.hosts.values())
+                    .any(|object| object.value["attributes"][&attribute] == *tag_id)
+            });
+        if removed_tag_in_use && body["repair"] != json!(true) {
+            return Response::problem(
+                401,
+                &format!(
+                    "Updating this host tag group \"{}\" requires additional authorization",
+                    id
+                ),
+                "The host tag group you intend to edit is used by other instances. You must \
+                 authorize Checkmk to update the relevant instances using the repair parameter",
+            );
+        }
+        self.update_object(request, domain_type, id, body)
+    }
+
+    fn delete_object(&mut self, domain_type: &DomainType, id: &str) -> Response {
+        let key = (domain_type.name.to_owned(), id.to_owned());
+        if self.objects.remove(&key).is_none() {
+            return not_found(domain_type.kind, id);
+        }
+        self.record_change(
+            &format!("delete-{}", domain_type.kind),
+            format!("Deleted {} {}", domain_type.kind, id),
+        );
+        Response::no_content()
+    }
+
+    fn rule_response(&self, rule: &Rule) -> Value {
+        let folder_index = self
+            .rules
+            .iter()
+            .filter(|other| other.ruleset == rule.ruleset && other.folder == rule.folder)
+            .position(|other| other.id == rule.id);
+        let mut extensions = rule.value.clone();
+        extensions["ruleset"] = json!(rule.ruleset);
+        extensions["folder"] = json!(rule.folder);
+        extensions["folder_index"] = json!(folder_index);
+        json!({
+            "id": rule.id,
+            "extensions": extensions,
+        })
+    }
+
+    fn list_rules(&self, ruleset: &str) -> Response {
+        Response::json(
+            200,
+            json!({
+                "id": ruleset,
+                "value": self.rules(ruleset),
+            }),
+        )
+    }
+
+    fn list_used_rulesets(&self) -> Response {
+        let rulesets = self
+            .rules
+            .iter()
+            .map(|rule| rule.ruleset.as_str())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|ruleset| {
+                json!({
+                    "id": ruleset,
+                    "title": ruleset,
+                    "extensions": {
+                        "name": ruleset,
+                        "number_of_rules": self.rules(ruleset).len(),
+                    },
+                })
+            })
+            .collect::<Vec<_>>();
+        Response::json(200, json!({"value": rulesets}))
+    }
+
+    fn show_rule(&self, id: &str) -> Response {
+        match self.rules.iter().find(|rule| rule.id == id) {
+            Some(rule) => Response::json(200, self.rule_response(rule)),
+            None => not_found("Rule", id),
+        }
+    }
+
+    fn create_rule(&mut self, body: &Value) -> Response {
+        let (Some(ruleset), Some(folder), Some(value_raw)) = (
+            body["ruleset"].as_str(),
+            body["folder"].as_str(),
+            body["value_raw"].as_str(),
+        ) else {
+            return bad_request("ruleset, folder and value_raw are required");
+        };
+        let folder = folder_path(folder);
+        if !self.folders.contains_key(&folder) {
+            return bad_request(format!("folder {} does not exist", folder));
+        }
+        self.revision += 1;
+        let rule = Rule {
+            id: format!("{:08x}-0000-4000-8000-000000000000", self.revision),
+            ruleset: ruleset.to_owned(),
+            folder: folder.clone(),
+            value: json!({
+                "properties": body.get("properties").cloned().unwrap_or_else(|| json!({})),
+                "conditions": body.get("conditions").cloned().unwrap_or_else(|| json!({})),
+                "value_raw": value_raw,
+            }),
+        };
+        self.rules.push(rule);
+        let response = Response::json(200, self.rule_response(self.rules.last().unwrap()));
+        self.record_change(
+            "new-rule",
+            format!(
+                "Created new rule in ruleset {} in folder {}",
+                ruleset, folder
+            ),
+        );
+        response
+    }
+
+    fn delete_rule(&mut self, id: &str) -> Response {
+        let Some(position) = self.rules.iter().position(|rule| rule.id == id) else {
+            return not_found("Rule", id);
+        };
+        let rule = self.rules.remove(position);
+        self.record_change(
+            "delete-rule",
+            format!(
+                "Deleted rule in ruleset {} in folder {}",
+                rule.ruleset, rule.folder
+            ),
+        );
+        Response::no_content()
+    }
+
+    fn activate_changes(&mut self, request: &Request) -> Response {
+        match request.headers.get("if-match") {
+            None => {
+                return Response::problem(
+                    428,
+                    "Precondition Required",
+                    "If-Match header is missing",
+                )
+            }
+            Some(if_match) if *if_match != self.pending_changes_etag() && if_match != "*" => {
+                return Response::problem(
+                    412,
+                    "Precondition Failed",
+                    "The pending changes have changed in the meantime",
+                )
+            }
+            Some(_) => {}
+        }
+        if self.pending_changes.is_empty() {
+            return Response::problem(
+                422,
+                "No pending changes",
+                "There are no changes to be activated.",
+            );
+        }
+        self.pending_changes.clear();
+        self.activations += 1;
+        Response::json(
+            200,
+            json!({
+                "id": format!("activation-{}", self.activations),
+                "title": "Activation status: In progress.",
+            }),
+        )
+    }
+}
@@ -0,0 +1,198 @@
+// Copyright 2024 TAKKT Industrial & Packaging GmbH
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! An in-memory fake of the checkmk REST API, served on a local port, to test checkmate against.
+//!
+//! Only the parts of the API needed to apply folders, hosts, rules, users, host, service and
+//! contact groups, time periods, passwords, host tag groups and aux tags are implemented,
+//! including ETags and checkmk's 401, 404, 412 and 422 responses. Requests to any other endpoint
+//! are answered with `501 Not Implemented`.
+
+mod api;
+mod http;
+
+use api::State;
+use http::{
+    Request,
+    Response,
+};
+use serde_json::Value;
+use std::{
+    net::{
+        SocketAddr,
+        TcpListener,
+        TcpStream,
+    },
+    sync::{
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
+        Arc,
+        Mutex,
+    },
+    thread::JoinHandle,
+};
+
+/// A fake checkmk server, which is shut down when dropped.
+pub struct FakeCheckmk {
+    address: SocketAddr,
+    site: String,
+    state: Arc<Mutex<State>>,
+    shutdown: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl FakeCheckmk {
+    /// Start a server for the site `cmk`, which only contains the root folder.
+    pub fn start() -> Self {
+        Self::start_with_site("cmk")
+    }
+
+    pub fn start_with_site<S: Into<String>>(site: S) -> Self {
+        let listener =
+            TcpListener::bind("127.0.0.1:0").expect("failed to bind the fake checkmk server");
+        let address = listener.local_addr().expect("listener has an address");
+        let site = site.into();
+        let state = Arc::new(Mutex::new(State::default()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let thread = std::thread::spawn({
+            let api_base_path = format!("/{}/check_mk/api/1.0", site);
+            let state = state.clone();
+            let shutdown = shutdown.clone();
+            move || {
+                for stream in listener.incoming() {
+                    if shutdown.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let Ok(stream) = stream else {
+                        continue;
+                    };
+                    let api_base_path = api_base_path.clone();
+                    let state = state.clone();
+                    std::thread::spawn(move || handle(stream, &api_base_path, &state));
+                }
+            }
+        });
+
+        Self {
+            address,
+            site,
+            state,
+            shutdown,
+            thread: Some(thread),
+        }
+    }
+
+    /// The URL of the server, without the site.
+    pub fn server_url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    pub fn site(&self) -> &str {
+        &self.site
+    }
+
+    /// All changes made through the API, in the order they were made, including the ones that were
+    /// activated already.
+    pub fn changes(&self) -> Vec<String> {
+        self.state().changes().to_vec()
+    }
+
+    /// Return all changes made through the API so far, and forget about them.
+    pub fn take_changes(&self) -> Vec<String> {
+        self.state().take_changes()
+    }
+
+    /// How often pending changes were activated.
+    pub fn activations(&self) -> usize {
+        self.state().activations()
+    }
+
+    /// Let the next request modifying an object fail with `412 Precondition Failed`, as if the
+    /// object was modified by someone else in the meantime.
+    pub fn conflict_on_next_update(&self) {
+        self.state().conflict_on_next_update();
+    }
+
+    /// The folder at the given path, as it is returned by the API.
+    pub fn folder(&self, path: &str) -> Option<Value> {
+        self.state().folder(path)
+    }
+
+    /// The host, as it is returned by the API.
+    pub fn host(&self, host_name: &str) -> Option<Value> {
+        self.state().host(host_name)
+    }
+
+    /// The rules of the ruleset in all folders, as they are returned by the API.
+    pub fn rules(&self, ruleset: &str) -> Vec<Value> {
+        self.state().rules(ruleset)
+    }
+
+    /// Any other object, like a user, by its domain type and ID, as it is returned by the API.
+    pub fn object(&self, domain_type: &str, id: &str) -> Option<Value> {
+        self.state().object(domain_type, id)
+    }
+
+    /// Replace the attributes of an existing host, like a user editing it in the UI would.
+    pub fn set_host_attributes(&self, host_name: &str, attributes: Value) {
+        self.state().set_host_attributes(host_name, attributes);
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .expect("state of the fake checkmk server is poisoned")
+    }
+}
+
+impl Drop for FakeCheckmk {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // Wake up the listener, so it notices the shutdown.
+        let _ = TcpStream::connect(self.address);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn handle(stream: TcpStream, api_base_path: &str, state: &Mutex<State>) {
+    let Ok(request) = Request::read_from(&stream) else {
+        return;
+    };
+    let response = if !request
+        .headers
+        .get("authorization")
+        .is_some_and(|authorization| authorization.starts_with("Bearer "))
+    {
+        Response::problem(401, "Unauthorized", "Authorization header is missing")
+    } else if let Some(endpoint) = request.path.strip_prefix(api_base_path) {
+        state
+            .lock()
+            .expect("state of the fake checkmk server is poisoned")
+            .handle(&request, endpoint)
+    } else {
+        Response::problem(
+            404,
+            "Not Found",
+            format!("{} is not part of the REST API of the site", request.path),
+        )
+    };
+    let _ = response.write_to(&stream);
+}